@@ -0,0 +1,67 @@
+//! Throughput of the pull-based [`Lexer`] compared to the threaded [`token_stream`].
+//!
+//! usage: cargo bench -p js2py_lexer
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use js2py_lexer::lexer::{token_stream, Lexer};
+
+const CASES: [&str; 4] = [
+    include_str!("../../js2py_translator/case/Astar.js"),
+    include_str!("../../js2py_translator/case/calculator.js"),
+    include_str!("../../js2py_translator/case/dp.js"),
+    include_str!("../../js2py_translator/case/insertion-sort.js"),
+];
+
+/// Many small files, the shape of a batch job
+fn small_files() -> Vec<String> {
+    CASES
+        .iter()
+        .cycle()
+        .take(1000)
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// A single large file
+fn large_file() -> String {
+    CASES.concat().repeat(250)
+}
+
+fn bench_corpus(c: &mut Criterion, name: &str, corpus: &[String]) {
+    let bytes = corpus.iter().map(|s| s.len() as u64).sum();
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(bytes));
+    group.bench_with_input(
+        BenchmarkId::new("lexer", corpus.len()),
+        corpus,
+        |b, corpus| {
+            b.iter(|| {
+                corpus
+                    .iter()
+                    .map(|input| Lexer::new(black_box(input)).count())
+                    .sum::<usize>()
+            })
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("token_stream", corpus.len()),
+        corpus,
+        |b, corpus| {
+            b.iter(|| {
+                corpus
+                    .iter()
+                    .map(|input| token_stream(black_box(input)).iter().count())
+                    .sum::<usize>()
+            })
+        },
+    );
+    group.finish();
+}
+
+fn lexer_benchmark(c: &mut Criterion) {
+    bench_corpus(c, "small_files", &small_files());
+    bench_corpus(c, "large_file", &[large_file()]);
+}
+
+criterion_group!(benches, lexer_benchmark);
+criterion_main!(benches);
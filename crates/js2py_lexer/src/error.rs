@@ -0,0 +1,41 @@
+//! Diagnostics reported by the lexer.
+
+use std::fmt;
+
+/// The kind of a [`LexError`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LexErrorKind {
+    /// A string literal that is not closed before a line terminator or the end of input
+    UnterminatedString,
+    /// An invalid escape sequence in a string literal, e.g. `"\x4"`
+    BadEscape,
+    /// A legacy octal escape sequence, e.g. `"\01"`
+    OctalLiteral,
+    /// A numeric literal with missing or invalid digits, e.g. `0x`
+    InvalidNumber,
+    /// A character that cannot start any token
+    UnexpectedCharacter,
+    /// A template literal that is not closed before the end of input
+    UnterminatedTemplate,
+    /// A regular expression literal that is not closed before a line terminator
+    UnterminatedRegExp,
+    /// A multi-line comment that is not closed before the end of input
+    UnterminatedComment,
+}
+
+/// A lexical error, pointing at the bytes `start..end` of the input
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub start: usize,
+    pub end: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Lex error at position {}: {}", self.start, self.message)
+    }
+}
+
+impl std::error::Error for LexError {}
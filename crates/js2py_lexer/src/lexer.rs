@@ -1,42 +1,105 @@
+use crate::error::{LexError, LexErrorKind};
 use crate::statefn::StateFn;
 use crate::token::{Token, Type};
+use std::collections::VecDeque;
 use std::sync::{mpsc, Arc};
 
 /// Create a token stream from the input string
 ///
+/// The tokens are produced by a [`Lexer`] running on a background thread. Prefer iterating a
+/// [`Lexer`] directly unless the tokens need to be consumed from another thread.
+///
+/// Each `LexerError` token is delivered as the [`LexError`] it stands for.
+///
 /// * `input`: the input string to tokenize
-pub fn token_stream(input: &str) -> mpsc::Receiver<Token> {
+pub fn token_stream(input: &str) -> mpsc::Receiver<Result<Token, LexError>> {
     let (tx, rx) = mpsc::channel();
-    let mut l = Lexer {
-        input: Arc::from(input),
-        start: 0,
-        pos: 0,
-        initial_state: StateFn::default(),
-        sender: tx,
-    };
-    std::thread::spawn(move || l.run());
+    let input: Arc<str> = Arc::from(input);
+    std::thread::spawn(move || {
+        let mut lexer = Lexer::new(&input);
+        let mut reported = 0;
+        while let Some(token) = lexer.next() {
+            let item = if token.typ == Type::LexerError {
+                reported += 1;
+                Err(lexer.errors()[reported - 1].clone())
+            } else {
+                Ok(token)
+            };
+            if tx.send(item).is_err() {
+                return;
+            }
+        }
+    });
     rx
 }
 
 /// Lexer context
-pub struct Lexer {
-    input: Arc<str>,
+///
+/// The lexer is pull-based: every call to [`Iterator::next`] runs the state functions until at
+/// least one token has been emitted, so no thread or channel is involved.
+pub struct Lexer<'a> {
+    input: &'a str,
     start: usize,
     pos: usize,
-    initial_state: StateFn,
-    sender: mpsc::Sender<Token>,
-    // error: Option<String>,
+    /// The state function to run next, `None` once the input is exhausted
+    state: Option<StateFn>,
+    /// Tokens emitted by the state functions but not yet handed out
+    pending: VecDeque<Token>,
+    /// Errors reported so far, each one is paired with a `LexerError` token
+    errors: Vec<LexError>,
+    /// Number of unclosed `{` in each template substitution we are in, innermost last
+    template_braces: Vec<usize>,
+    /// The type of the last token that is not a line terminator
+    prev_significant: Option<Type>,
+    /// Whether a line terminator was seen since the last token that is not a line terminator
+    new_line: bool,
+    /// Whether each unclosed `(` opens the head of an `if`, `while` or `for`, innermost last
+    parens: Vec<bool>,
+    /// Whether the last token that is not a line terminator is a `)` closing such a head, after
+    /// which a statement and so a regular expression can start
+    closes_head: bool,
 }
 
-impl Lexer {
-    pub fn run(&mut self) {
-        let mut f = self.initial_state.clone();
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            start: 0,
+            pos: 0,
+            state: Some(StateFn::default()),
+            pending: VecDeque::new(),
+            errors: Vec::new(),
+            template_braces: Vec::new(),
+            prev_significant: None,
+            new_line: false,
+            parens: Vec::new(),
+            closes_head: false,
+        }
+    }
+
+    /// The errors reported so far
+    ///
+    /// A `LexerError` token is emitted for every error, in the same order.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    /// Run the state functions until there are no more state functions to run
+    pub fn run(&mut self) -> Vec<Token> {
+        self.collect()
+    }
 
-        // Run the state functions until there are no more state functions to run
-        // This pattern can decouple the lexer from the state functions and make it easier to
-        // extend the lexer with new state functions.
-        while let Some(next_f) = f.call(self) {
-            f = next_f;
+    /// Run a single state function, returns false if there are no more state functions to run
+    ///
+    /// This pattern can decouple the lexer from the state functions and make it easier to
+    /// extend the lexer with new state functions.
+    fn step_state(&mut self) -> bool {
+        match self.state.take() {
+            Some(f) => {
+                self.state = f.call(self);
+                true
+            }
+            None => false,
         }
     }
 
@@ -52,18 +115,46 @@ impl Lexer {
     }
     /// Send a token (without updating the start position)
     pub(crate) fn send(&mut self, typ: Type) {
-        let _ = self.sender.send(Token {
+        let is_on_new_line = self.new_line;
+        if typ == Type::LineTerminator {
+            self.new_line = true;
+        } else {
+            match typ {
+                Type::LParen => self.parens.push(matches!(
+                    self.prev_significant,
+                    Some(Type::If | Type::While | Type::For)
+                )),
+                Type::RParen => self.closes_head = self.parens.pop().unwrap_or(false),
+                _ => {}
+            }
+            self.prev_significant = Some(typ);
+            self.new_line = false;
+        }
+        self.pending.push_back(Token {
             typ,
             start: self.start,
             end: self.pos,
+            is_on_new_line,
         });
     }
+    /// Record a line terminator that is not emitted as a token, such as one in a comment
+    pub(crate) fn mark_new_line(&mut self) {
+        self.new_line = true;
+    }
     pub(crate) fn ignore(&mut self) {
         self.start = self.pos;
     }
     pub(crate) fn peek(&self) -> Option<char> {
         self.input[self.pos..].chars().next()
     }
+    /// Peek the character after the next one
+    pub(crate) fn peek_second(&self) -> Option<char> {
+        self.input[self.pos..].chars().nth(1)
+    }
+    /// Whether the remaining input starts with `s`
+    pub(crate) fn input_starts_with(&self, s: &str) -> bool {
+        self.input[self.pos..].starts_with(s)
+    }
     pub(crate) fn step(&mut self) -> Option<char> {
         self.pos += self.peek()?.len_utf8();
         self.peek()
@@ -107,10 +198,92 @@ impl Lexer {
         }
         accepted
     }
-    pub(crate) fn error(&self, msg: &str) {
-        eprintln!("Lex error at position {}: {}", self.pos, msg);
+    /// Record an error spanning the current value
+    pub(crate) fn error(&mut self, kind: LexErrorKind, msg: &str) {
+        self.errors.push(LexError {
+            kind,
+            start: self.start,
+            end: self.pos,
+            message: msg.to_string(),
+        });
+    }
+    /// Enter the substitution of a template literal, after `${`
+    pub(crate) fn enter_template_substitution(&mut self) {
+        self.template_braces.push(0);
+    }
+    /// Track a `{`, so that its matching `}` does not end a template substitution
+    pub(crate) fn open_brace(&mut self) {
+        if let Some(depth) = self.template_braces.last_mut() {
+            *depth += 1;
+        }
+    }
+    /// Track a `}`, returns true if it ends the current template substitution
+    pub(crate) fn close_brace(&mut self) -> bool {
+        match self.template_braces.last_mut() {
+            Some(0) => {
+                self.template_braces.pop();
+                true
+            }
+            Some(depth) => {
+                *depth -= 1;
+                false
+            }
+            None => false,
+        }
+    }
+    /// Whether a `/` starts a regular expression rather than a division, decided by the
+    /// previous token: a division can only follow the end of an operand, which a `)` closing
+    /// the head of an `if`, `while` or `for` is not.
+    pub(crate) fn regexp_allowed(&self) -> bool {
+        if self.prev_significant == Some(Type::RParen) && self.closes_head {
+            return true;
+        }
+        !matches!(
+            self.prev_significant,
+            Some(
+                Type::Identifier
+                    | Type::Decimal
+                    | Type::Hex
+                    | Type::Str
+                    | Type::NoSubstitutionTemplate
+                    | Type::TemplateTail
+                    | Type::RegExp
+                    | Type::Null
+                    | Type::True
+                    | Type::False
+                    | Type::This
+                    | Type::Super
+                    | Type::RParen
+                    | Type::RBrack
+                    | Type::RCurly
+                    | Type::Plus2
+                    | Type::Minus2
+                    // contextual keywords are usually identifiers
+                    | Type::Async
+                    | Type::From
+                    | Type::Get
+                    | Type::Meta
+                    | Type::Of
+                    | Type::Set
+                    | Type::Target
+                    | Type::Accessor
+            )
+        )
     }
     pub(crate) fn eof(&self) -> bool {
         self.pos >= self.input.len()
     }
 }
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        while self.pending.is_empty() {
+            if !self.step_state() {
+                return None;
+            }
+        }
+        self.pending.pop_front()
+    }
+}
@@ -1,3 +1,4 @@
+pub mod error;
 pub mod lexer;
 pub mod statefn;
 pub mod statefn_impl;
@@ -14,8 +14,8 @@ fn main() {
     }
 
     let input = std::fs::read_to_string(filename).expect("Failed to read file");
-    let token_stream = js2py_lexer::lexer::token_stream(&input);
-    for token in token_stream.iter()
+    let mut lexer = js2py_lexer::lexer::Lexer::new(&input);
+    for token in lexer.by_ref()
     // .filter(|t| t.typ != js2py_lexer::token::Type::LineTerminator)
     {
         // println!("('{:?}', '{}')", token.typ, &input[token.start..token.end]);
@@ -25,6 +25,9 @@ fn main() {
             &input[token.start..token.end].replace("\n", "\\n")
         );
     }
+    for error in lexer.errors() {
+        eprintln!("{}", error);
+    }
 }
 
 #[test]
@@ -33,7 +36,7 @@ fn test1() {
     use js2py_lexer::*;
     let input = "var x = 10;";
     let token_stream = lexer::token_stream(input);
-    let tokens: Vec<_> = token_stream.iter().collect();
+    let tokens: Vec<_> = token_stream.iter().collect::<Result<_, _>>().unwrap();
     assert_eq!(tokens.len(), 6);
     assert_eq!(tokens[0].typ, Type::Var);
     assert_eq!(tokens[1].typ, Type::Identifier);
@@ -42,3 +45,175 @@ fn test1() {
     assert_eq!(tokens[4].typ, Type::Semicolon);
     assert_eq!(tokens[5].typ, Type::EOF);
 }
+
+#[test]
+fn test_lexer_matches_token_stream() {
+    use js2py_lexer::*;
+    let input = "function f(a) {\n  return a * 2; // double\n}";
+    let tokens: Vec<_> = lexer::Lexer::new(input).collect();
+    let streamed: Vec<_> = lexer::token_stream(input).iter().collect();
+    assert_eq!(
+        tokens,
+        streamed.into_iter().collect::<Result<Vec<_>, _>>().unwrap()
+    );
+    assert_eq!(tokens.last().unwrap().typ, token::Type::EOF);
+
+    // errors are delivered in place of their `LexerError` tokens
+    let input = "a @ 'b";
+    let mut lexer = lexer::Lexer::new(input);
+    let tokens: Vec<_> = lexer.by_ref().collect();
+    let streamed: Vec<_> = lexer::token_stream(input).iter().collect();
+    assert_eq!(tokens.len(), streamed.len());
+    let errors: Vec<_> = streamed.into_iter().filter_map(Result::err).collect();
+    assert_eq!(errors, lexer.errors());
+}
+
+#[test]
+fn test_lex_errors() {
+    use js2py_lexer::error::LexErrorKind;
+    use js2py_lexer::*;
+    let cases = [
+        ("'abc", LexErrorKind::UnterminatedString),
+        ("'\\x4'", LexErrorKind::BadEscape),
+        ("'\\01'", LexErrorKind::OctalLiteral),
+        ("a @ b", LexErrorKind::UnexpectedCharacter),
+        ("/* abc", LexErrorKind::UnterminatedComment),
+    ];
+    for (input, kind) in cases {
+        let mut lexer = lexer::Lexer::new(input);
+        let tokens: Vec<_> = lexer.by_ref().collect();
+        assert!(tokens.iter().any(|t| t.typ == token::Type::LexerError));
+        // lexing resumes after an error
+        assert_eq!(tokens.last().unwrap().typ, token::Type::EOF);
+        assert_eq!(lexer.errors().len(), 1);
+        assert_eq!(lexer.errors()[0].kind, kind, "{}", input);
+    }
+    let mut lexer = lexer::Lexer::new("a @ b");
+    lexer.by_ref().for_each(drop);
+    assert_eq!((lexer.errors()[0].start, lexer.errors()[0].end), (2, 3));
+}
+
+#[test]
+fn test_unicode_identifiers() {
+    use js2py_lexer::token::Type;
+    use js2py_lexer::*;
+    let input = r"item2 $el _0x1f café ａｂｃ \u0061bc a\u{62}c \u0076ar";
+    let tokens: Vec<_> = lexer::Lexer::new(input)
+        .filter(|t| t.typ != Type::EOF)
+        .collect();
+    assert_eq!(tokens.len(), 8);
+    assert!(tokens.iter().all(|t| t.typ == Type::Identifier));
+}
+
+#[test]
+fn test_numeric_literals() {
+    use js2py_lexer::token::Type;
+    use js2py_lexer::*;
+    let cases = [
+        ("0", Type::Decimal),
+        ("1_000_000", Type::Decimal),
+        (".5", Type::Decimal),
+        ("1.5e-3", Type::Decimal),
+        ("0.5", Type::Decimal),
+        ("0xFF", Type::Hex),
+        ("0b1010", Type::Binary),
+        ("0o777", Type::Octal),
+        ("10n", Type::BigInt),
+        ("0xFF_FFn", Type::BigInt),
+    ];
+    for (input, typ) in cases {
+        let tokens: Vec<_> = lexer::Lexer::new(input).collect();
+        assert_eq!(tokens.len(), 2, "{}", input);
+        assert_eq!(tokens[0].typ, typ, "{}", input);
+        assert_eq!(tokens[0].end, input.len(), "{}", input);
+    }
+    for input in ["1__0", "1_", "0x", "0b2", "017", "1.5n", "3in", "1e"] {
+        let tokens: Vec<_> = lexer::Lexer::new(input).collect();
+        // the whole literal is skipped
+        let types: Vec<_> = tokens.iter().map(|t| t.typ).collect();
+        assert_eq!(types, [Type::LexerError, Type::EOF], "{}", input);
+    }
+}
+
+#[test]
+fn test_is_on_new_line() {
+    use js2py_lexer::token::Type;
+    use js2py_lexer::*;
+    let input = "a b\nc /* \n */ d /* */ e // f\ng";
+    let flags: Vec<_> = lexer::Lexer::new(input)
+        .filter(|t| !matches!(t.typ, Type::LineTerminator | Type::EOF))
+        .map(|t| t.is_on_new_line)
+        .collect();
+    assert_eq!(flags, [false, false, true, true, false, true]);
+}
+
+#[test]
+fn test_spread_punctuator() {
+    use js2py_lexer::token::Type;
+    use js2py_lexer::*;
+    let tokens: Vec<_> = lexer::Lexer::new("[...a, b.c, .5]").collect();
+    let types: Vec<_> = tokens.iter().map(|t| t.typ).collect();
+    assert_eq!(
+        types,
+        [
+            Type::LBrack,
+            Type::Dot3,
+            Type::Identifier,
+            Type::Comma,
+            Type::Identifier,
+            Type::Dot,
+            Type::Identifier,
+            Type::Comma,
+            Type::Decimal,
+            Type::RBrack,
+            Type::EOF
+        ]
+    );
+}
+
+#[test]
+fn test_compound_operators() {
+    use js2py_lexer::token::Type;
+    use js2py_lexer::*;
+    let tokens: Vec<_> = lexer::Lexer::new("** **= ?? ??= &&= ||= ?. ? * && ?.5").collect();
+    let types: Vec<_> = tokens.iter().map(|t| t.typ).collect();
+    assert_eq!(
+        types,
+        [
+            Type::Star2,
+            Type::Star2Eq,
+            Type::Question2,
+            Type::Question2Eq,
+            Type::Amp2Eq,
+            Type::Pipe2Eq,
+            Type::QuestionDot,
+            Type::Question,
+            Type::Star,
+            Type::Amp2,
+            Type::Question,
+            Type::Decimal,
+            Type::EOF
+        ]
+    );
+}
+
+#[test]
+fn test_regexp_after_parenthesis() {
+    use js2py_lexer::token::Type;
+    use js2py_lexer::*;
+    let types = |source| {
+        lexer::Lexer::new(source)
+            .map(|t| t.typ)
+            .filter(|typ| matches!(typ, Type::RegExp | Type::Slash))
+            .collect::<Vec<_>>()
+    };
+    // a `)` closing the head of a statement is followed by a statement
+    assert_eq!(types("if (x) /re/.test(s)"), [Type::RegExp]);
+    assert_eq!(types("while (f(c)) /x/.exec(s)"), [Type::RegExp]);
+    assert_eq!(types("for (;;) /x/g.exec(s)"), [Type::RegExp]);
+    // any other `)` ends an operand
+    assert_eq!(
+        types("if ((a + b) / 2) c = (d) / e"),
+        [Type::Slash, Type::Slash]
+    );
+}
@@ -1,5 +1,6 @@
 //! The implementation of the state functions that the lexer uses to parse the input string.
 
+use crate::error::LexErrorKind;
 use crate::lexer::Lexer;
 use crate::statefn::StateFn;
 use crate::token::Type;
@@ -19,6 +20,31 @@ pub fn lex_start(lexer: &mut Lexer) -> Option<StateFn> {
 
     let c = lexer.peek();
 
+    // curly braces need to be tracked for template substitutions
+    match c.unwrap() {
+        '{' => {
+            lexer.open_brace();
+        }
+        '}' if lexer.close_brace() => {
+            return sf!(lex_template_continuation);
+        }
+        _ => {}
+    }
+
+    // a decimal number starting with a dot, e.g. `.5`
+    if c == Some('.') && lexer.peek_second().is_some_and(|c| c.is_ascii_digit()) {
+        return sf!(lex_decimal);
+    }
+
+    // spread and rest elements, `...`
+    if lexer.input_starts_with("...") {
+        lexer.step();
+        lexer.step();
+        lexer.step();
+        lexer.emit(Dot3);
+        return sf!(lex_start);
+    }
+
     // trivial cases for punctuators
     if let Some(next_f) = match c.unwrap() {
         ':' => Some(Colon),
@@ -32,7 +58,6 @@ pub fn lex_start(lexer: &mut Lexer) -> Option<StateFn> {
         ']' => Some(RBrack),
         '~' => Some(Tilde),
         '.' => Some(Dot),
-        '?' => Some(Question),
         _ => None,
     } {
         lexer.step();
@@ -53,10 +78,11 @@ pub fn lex_start(lexer: &mut Lexer) -> Option<StateFn> {
     }
 
     match c.unwrap() {
-        'a'..='z' | 'A'..='Z' | '_' => sf!(lex_identifier_or_keyword),
+        c if is_identifier_start(c) || c == '\\' => sf!(lex_identifier_or_keyword),
         '1'..='9' => sf!(lex_decimal),
         '0' => sf!(lex_zero),
         '"' | '\'' => sf!(lex_string_literal),
+        '`' => sf!(lex_template),
         '=' => sf!(lex_eq),
         '!' => sf!(lex_bang),
         '|' => sf!(lex_pipe),
@@ -64,6 +90,7 @@ pub fn lex_start(lexer: &mut Lexer) -> Option<StateFn> {
         '>' => sf!(lex_gt),
         '&' => sf!(lex_amp),
         '*' => sf!(lex_star),
+        '?' => sf!(lex_question),
         '/' => sf!(lex_slash),
         '+' => sf!(lex_plus),
         '-' => sf!(lex_minus),
@@ -83,57 +110,181 @@ pub fn lex_start(lexer: &mut Lexer) -> Option<StateFn> {
             lexer.ignore();
             sf!(lex_start)
         }
-        _ => lex_error(
-            lexer,
-            format!("Unexpected character: {}", c.unwrap()).as_str(),
-        ),
+        _ => {
+            lexer.step();
+            lex_error(
+                lexer,
+                LexErrorKind::UnexpectedCharacter,
+                format!("Unexpected character: {}", c.unwrap()).as_str(),
+            )
+        }
     }
 }
 
+/// IdentifierStartChar ::
+///   UnicodeIDStart
+///   $
+///   _
+pub fn is_identifier_start(c: char) -> bool {
+    c == '$' || c == '_' || unicode_ident::is_xid_start(c)
+}
+
+/// IdentifierPartChar ::
+///   UnicodeIDContinue
+///   $
+///   <ZWNJ>
+///   <ZWJ>
+pub fn is_identifier_part(c: char) -> bool {
+    c == '$' || c == '\u{200C}' || c == '\u{200D}' || unicode_ident::is_xid_continue(c)
+}
+
 fn lex_identifier_or_keyword(lexer: &mut Lexer) -> Option<StateFn> {
-    lexer.accept_run("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_");
+    let mut escaped = false;
+    while let Some(c) = lexer.peek() {
+        if c == '\\' {
+            // \u Hex4Digits or \u{ CodePoint }
+            lexer.step();
+            let valid = lexer.accept("u")
+                && if lexer.accept("{") {
+                    lexer.accept_run("0123456789abcdefABCDEF") && lexer.accept("}")
+                } else {
+                    (0..4).all(|_| lexer.accept("0123456789abcdefABCDEF"))
+                };
+            if !valid {
+                return lex_error(
+                    lexer,
+                    LexErrorKind::BadEscape,
+                    "Invalid Unicode escape sequence in identifier",
+                );
+            }
+            escaped = true;
+        } else if is_identifier_part(c) {
+            lexer.step();
+        } else {
+            break;
+        }
+    }
     let s = lexer.current();
-    let typ = Type::match_keyword(&s);
+    // an escaped keyword is never a keyword
+    let typ = if escaped {
+        Identifier
+    } else {
+        Type::match_keyword(&s)
+    };
     lexer.emit(typ);
     sf!(lex_start)
 }
 
 fn lex_zero(lexer: &mut Lexer) -> Option<StateFn> {
     lexer.step();
-    match lexer.peek() {
-        Some('x') | Some('X') => {
-            lexer.step(); // Skip the x
-            if lexer.accept_run("0123456789abcdefABCDEF") {
-                lexer.emit(Hex);
-                sf!(lex_start)
-            } else {
-                lex_error(lexer, "Expected hexadecimal number")
-            }
+    let (typ, digits) = match lexer.peek() {
+        Some('x' | 'X') => (Hex, "0123456789abcdefABCDEF"),
+        Some('o' | 'O') => (Octal, "01234567"),
+        Some('b' | 'B') => (Binary, "01"),
+        Some(c) if c.is_ascii_digit() || c == '_' => {
+            return lex_error(
+                lexer,
+                LexErrorKind::InvalidNumber,
+                "Decimal numbers may not start with a zero",
+            );
         }
-        Some('.') => {
+        _ => {
             lexer.back();
-            sf!(lex_decimal)
+            return sf!(lex_decimal);
         }
-        _ => {
-            lexer.emit(Decimal); // Emit number
-            sf!(lex_start)
+    };
+    lexer.step(); // Skip the prefix
+    match accept_digits(lexer, digits) {
+        Some(true) => {}
+        Some(false) => {
+            return lex_error(
+                lexer,
+                LexErrorKind::InvalidNumber,
+                format!("Expected {} digits", typ.to_str()).as_str(),
+            )
         }
+        None => return lex_invalid_separator(lexer),
     }
+    let typ = if lexer.accept("n") { BigInt } else { typ };
+    lex_number_end(lexer, typ)
 }
 
 fn lex_decimal(lexer: &mut Lexer) -> Option<StateFn> {
-    let has_int = lexer.accept_run("0123456789");
+    let Some(has_int) = accept_digits(lexer, "0123456789") else {
+        return lex_invalid_separator(lexer);
+    };
+    let mut is_integer = true;
     if lexer.accept(".") {
-        let has_frac = lexer.accept_run("0123456789");
+        is_integer = false;
+        let Some(has_frac) = accept_digits(lexer, "0123456789") else {
+            return lex_invalid_separator(lexer);
+        };
         if !has_int && !has_frac {
-            return lex_error(lexer, "Expected decimal number");
+            return lex_error(
+                lexer,
+                LexErrorKind::InvalidNumber,
+                "Expected decimal number",
+            );
         }
     }
     if lexer.accept("eE") {
+        is_integer = false;
         lexer.accept("+-");
-        lexer.accept_run("0123456789");
+        match accept_digits(lexer, "0123456789") {
+            Some(true) => {}
+            Some(false) => {
+                return lex_error(lexer, LexErrorKind::InvalidNumber, "Expected exponent")
+            }
+            None => return lex_invalid_separator(lexer),
+        }
+    }
+    let typ = if is_integer && lexer.accept("n") {
+        BigInt
+    } else {
+        Decimal
+    };
+    lex_number_end(lexer, typ)
+}
+
+/// Accept digits with optional `_` separators between them, returns whether any digit was
+/// accepted or None if a separator is not followed by a digit
+fn accept_digits(lexer: &mut Lexer, digits: &str) -> Option<bool> {
+    let mut accepted = false;
+    loop {
+        if lexer.accept_run(digits) {
+            accepted = true;
+        } else if accepted && lexer.accept("_") {
+            if !lexer.accept_run(digits) {
+                return None;
+            }
+        } else {
+            return Some(accepted);
+        }
+    }
+}
+
+fn lex_invalid_separator(lexer: &mut Lexer) -> Option<StateFn> {
+    lex_error(
+        lexer,
+        LexErrorKind::InvalidNumber,
+        "Numeric separators are only allowed between digits",
+    )
+}
+
+/// Emit a numeric literal, which may not be directly followed by an identifier or a digit
+fn lex_number_end(lexer: &mut Lexer, typ: Type) -> Option<StateFn> {
+    if lexer
+        .peek()
+        .is_some_and(|c| c.is_ascii_digit() || is_identifier_start(c) || c == '\\')
+    {
+        lexer.step();
+        return lex_error(
+            lexer,
+            LexErrorKind::InvalidNumber,
+            "An identifier or a digit cannot immediately follow a numeric literal",
+        );
     }
-    lexer.emit(Decimal);
+    lexer.emit(typ);
     sf!(lex_start)
 }
 
@@ -171,19 +322,31 @@ fn lex_string_literal(lexer: &mut Lexer) -> Option<StateFn> {
                         lexer.step(); // Skip the zero
                         if let Some(c) = lexer.peek() {
                             if c.is_ascii_digit() {
-                                return lex_error(lexer, "Octal literals are not allowed");
+                                return lex_error(
+                                    lexer,
+                                    LexErrorKind::OctalLiteral,
+                                    "Octal literals are not allowed",
+                                );
                             }
                         }
                     }
                     '1'..='9' => {
-                        return lex_error(lexer, "Octal literals are not allowed");
+                        return lex_error(
+                            lexer,
+                            LexErrorKind::OctalLiteral,
+                            "Octal literals are not allowed",
+                        );
                     }
                     'x' => {
                         lexer.step(); // Skip the x
                         if check_n_hex(lexer, 2) {
                             continue;
                         } else {
-                            return lex_error(lexer, "Expected hexadecimal number");
+                            return lex_error(
+                                lexer,
+                                LexErrorKind::BadEscape,
+                                "Expected hexadecimal number",
+                            );
                         }
                     }
                     'u' => {
@@ -191,21 +354,83 @@ fn lex_string_literal(lexer: &mut Lexer) -> Option<StateFn> {
                         if check_n_hex(lexer, 4) {
                             continue;
                         } else {
-                            return lex_error(lexer, "Expected hexadecimal number");
+                            return lex_error(
+                                lexer,
+                                LexErrorKind::BadEscape,
+                                "Expected hexadecimal number",
+                            );
                         }
                     }
                     _ => {}
                 }
             } else {
-                return lex_error(lexer, "Unexpected end of input");
+                return lex_error(
+                    lexer,
+                    LexErrorKind::UnterminatedString,
+                    "Unexpected end of input",
+                );
             }
         } else if c == '\n' || c == '\r' || c == '\u{2028}' || c == '\u{2029}' {
-            return lex_error(lexer, "Unexpected line terminator");
+            return lex_error(
+                lexer,
+                LexErrorKind::UnterminatedString,
+                "Unexpected line terminator",
+            );
         }
 
         lexer.step();
     }
-    lex_error(lexer, "Unexpected end of input")
+    lex_error(
+        lexer,
+        LexErrorKind::UnterminatedString,
+        "Unexpected end of input",
+    )
+}
+
+/// Lex a template literal from its opening backtick
+fn lex_template(lexer: &mut Lexer) -> Option<StateFn> {
+    lexer.step(); // Skip the opening backtick
+    lex_template_chars(lexer, NoSubstitutionTemplate, TemplateHead)
+}
+
+/// Lex the rest of a template literal from the `}` that ends a substitution
+fn lex_template_continuation(lexer: &mut Lexer) -> Option<StateFn> {
+    lexer.step(); // Skip the closing curly brace
+    lex_template_chars(lexer, TemplateTail, TemplateMiddle)
+}
+
+/// Lex template characters until the closing backtick (emits `end`) or the start of a
+/// substitution (emits `substitution`)
+fn lex_template_chars(lexer: &mut Lexer, end: Type, substitution: Type) -> Option<StateFn> {
+    while let Some(c) = lexer.peek() {
+        match c {
+            '`' => {
+                lexer.step(); // Skip the closing backtick
+                lexer.emit(end);
+                return sf!(lex_start);
+            }
+            '$' => {
+                lexer.step();
+                if lexer.accept("{") {
+                    lexer.emit(substitution);
+                    lexer.enter_template_substitution();
+                    return sf!(lex_start);
+                }
+            }
+            '\\' => {
+                lexer.step(); // Skip the backslash
+                lexer.step(); // Skip the escaped character
+            }
+            _ => {
+                lexer.step();
+            }
+        }
+    }
+    lex_error(
+        lexer,
+        LexErrorKind::UnterminatedTemplate,
+        "Unterminated template literal",
+    )
 }
 
 fn lex_eq(lexer: &mut Lexer) -> Option<StateFn> {
@@ -213,6 +438,7 @@ fn lex_eq(lexer: &mut Lexer) -> Option<StateFn> {
     match lexer.current().as_str() {
         "===" => lexer.emit(Eq3),
         "==" => lexer.emit(Eq2),
+        "=" if lexer.accept(">") => lexer.emit(Arrow),
         _ => lexer.emit(Eq),
     }
     sf!(lex_start)
@@ -221,7 +447,11 @@ fn lex_eq(lexer: &mut Lexer) -> Option<StateFn> {
 fn lex_pipe(lexer: &mut Lexer) -> Option<StateFn> {
     lexer.step();
     if lexer.accept("|") {
-        lexer.emit(Pipe2);
+        if lexer.accept("=") {
+            lexer.emit(Pipe2Eq);
+        } else {
+            lexer.emit(Pipe2);
+        }
     } else if lexer.accept("=") {
         lexer.emit(PipeEq);
     } else {
@@ -283,7 +513,11 @@ fn lex_gt(lexer: &mut Lexer) -> Option<StateFn> {
 fn lex_amp(lexer: &mut Lexer) -> Option<StateFn> {
     lexer.step();
     if lexer.accept("&") {
-        lexer.emit(Amp2);
+        if lexer.accept("=") {
+            lexer.emit(Amp2Eq);
+        } else {
+            lexer.emit(Amp2);
+        }
     } else if lexer.accept("=") {
         lexer.emit(AmpEq);
     } else {
@@ -294,7 +528,13 @@ fn lex_amp(lexer: &mut Lexer) -> Option<StateFn> {
 
 fn lex_star(lexer: &mut Lexer) -> Option<StateFn> {
     lexer.step();
-    if lexer.accept("=") {
+    if lexer.accept("*") {
+        if lexer.accept("=") {
+            lexer.emit(Star2Eq);
+        } else {
+            lexer.emit(Star2);
+        }
+    } else if lexer.accept("=") {
         lexer.emit(StarEq);
     } else {
         lexer.emit(Star);
@@ -302,21 +542,79 @@ fn lex_star(lexer: &mut Lexer) -> Option<StateFn> {
     sf!(lex_start)
 }
 
+fn lex_question(lexer: &mut Lexer) -> Option<StateFn> {
+    lexer.step();
+    if lexer.accept("?") {
+        if lexer.accept("=") {
+            lexer.emit(Question2Eq);
+        } else {
+            lexer.emit(Question2);
+        }
+    } else if lexer.peek() == Some('.') && !lexer.peek_second().is_some_and(|c| c.is_ascii_digit())
+    {
+        // `a?.5:b` is a conditional expression with a decimal number
+        lexer.step();
+        lexer.emit(QuestionDot);
+    } else {
+        lexer.emit(Question);
+    }
+    sf!(lex_start)
+}
+
 fn lex_slash(lexer: &mut Lexer) -> Option<StateFn> {
     lexer.step();
-    if lexer.accept("=") {
-        lexer.emit(SlashEq);
-        sf!(lex_start)
-    } else if lexer.accept("/") {
+    if lexer.accept("/") {
         sf!(lex_single_line_comment)
     } else if lexer.accept("*") {
         sf!(lex_multi_line_comment)
+    } else if lexer.regexp_allowed() {
+        sf!(lex_regexp)
+    } else if lexer.accept("=") {
+        lexer.emit(SlashEq);
+        sf!(lex_start)
     } else {
         lexer.emit(Slash);
         sf!(lex_start)
     }
 }
 
+/// Lex a regular expression literal after its opening slash
+fn lex_regexp(lexer: &mut Lexer) -> Option<StateFn> {
+    let mut in_class = false;
+    while let Some(c) = lexer.peek() {
+        match c {
+            '\n' | '\r' | '\u{2028}' | '\u{2029}' => break,
+            '\\' => {
+                lexer.step(); // Skip the backslash
+                if matches!(
+                    lexer.peek(),
+                    None | Some('\n' | '\r' | '\u{2028}' | '\u{2029}')
+                ) {
+                    break;
+                }
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => {
+                lexer.step(); // Skip the closing slash
+                              // Accept the flags
+                while lexer.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                    lexer.step();
+                }
+                lexer.emit(RegExp);
+                return sf!(lex_start);
+            }
+            _ => {}
+        }
+        lexer.step();
+    }
+    lex_error(
+        lexer,
+        LexErrorKind::UnterminatedRegExp,
+        "Unterminated regular expression literal",
+    )
+}
+
 fn lex_plus(lexer: &mut Lexer) -> Option<StateFn> {
     lexer.step();
     if lexer.accept("=") {
@@ -370,7 +668,9 @@ fn lex_single_line_comment(lexer: &mut Lexer) -> Option<StateFn> {
         }
         lexer.step();
     }
-    lex_error(lexer, "Unexpected end of input")
+    // a single line comment may end at the end of input
+    lexer.ignore();
+    sf!(lex_start)
 }
 
 /// simplified
@@ -380,18 +680,62 @@ fn lex_multi_line_comment(lexer: &mut Lexer) -> Option<StateFn> {
         if c == '*' {
             lexer.step();
             if lexer.accept("/") {
+                // a multi-line comment containing a line terminator counts as one for ASI
+                if lexer
+                    .current()
+                    .contains(['\n', '\r', '\u{2028}', '\u{2029}'])
+                {
+                    lexer.mark_new_line();
+                }
                 lexer.ignore();
                 return sf!(lex_start);
             }
         }
         lexer.step();
     }
-    lex_error(lexer, "Unexpected end of input")
+    lex_error(
+        lexer,
+        LexErrorKind::UnterminatedComment,
+        "Unterminated multi-line comment",
+    )
 }
 
-// TODO: error handling
-fn lex_error(lexer: &mut Lexer, msg: &str) -> Option<StateFn> {
+/// Report an error and resume lexing after the current value
+///
+/// The rest of a malformed number or string is skipped, so that it is not lexed as code.
+fn lex_error(lexer: &mut Lexer, kind: LexErrorKind, msg: &str) -> Option<StateFn> {
+    lexer.error(kind, msg);
+    match kind {
+        LexErrorKind::InvalidNumber => {
+            while lexer
+                .peek()
+                .is_some_and(|c| is_identifier_part(c) || c == '.')
+            {
+                lexer.step();
+            }
+        }
+        LexErrorKind::BadEscape | LexErrorKind::OctalLiteral => {
+            if let Some(quote @ ('\'' | '"')) = lexer.current().chars().next() {
+                while let Some(c) = lexer.peek() {
+                    if matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}') {
+                        break;
+                    }
+                    lexer.step();
+                    if c == quote {
+                        break;
+                    }
+                    if c == '\\' {
+                        lexer.step();
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    if lexer.current().is_empty() {
+        lexer.step(); // make progress
+    }
     lexer.send(LexerError);
-    lexer.error(msg);
-    None
+    lexer.ignore();
+    sf!(lex_start)
 }
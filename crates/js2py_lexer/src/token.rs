@@ -3,6 +3,8 @@ pub struct Token {
     pub typ: Type,
     pub start: usize,
     pub end: usize,
+    /// Whether a line terminator, possibly inside a multi-line comment, precedes the token
+    pub is_on_new_line: bool,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -67,13 +69,16 @@ pub enum Type {
     // punctuator
     Amp,           // &
     Amp2,          // &&
+    Amp2Eq,        // &&=
     AmpEq,         // &=
+    Arrow,         // =>
     Bang,          // !
     Caret,         // ^
     CaretEq,       // ^=
     Colon,         // :
     Comma,         // ,
     Dot,           // .
+    Dot3,          // ...
     Eq,            // =
     Eq2,           // ==
     Eq3,           // ===
@@ -92,11 +97,15 @@ pub enum Type {
     PercentEq,     // %=
     Pipe,          // |
     Pipe2,         // ||
+    Pipe2Eq,       // ||=
     PipeEq,        // |=
     Plus,          // +
     Plus2,         // ++
     PlusEq,        // +=
     Question,      // ?
+    Question2,     // ??
+    Question2Eq,   // ??=
+    QuestionDot,   // ?.
     RAngle,        // >
     RBrack,        // ]
     RCurly,        // }
@@ -109,6 +118,8 @@ pub enum Type {
     ShiftRight3,   // >>>
     ShiftRight3Eq, // >>>=
     Star,          // *
+    Star2,         // **
+    Star2Eq,       // **=
     StarEq,        // *=
     Tilde,         // ~
     // div punctuator
@@ -124,9 +135,19 @@ pub enum Type {
     // Numeric Literals
     Decimal,
     Hex,
+    Binary,
+    Octal,
+    BigInt, // 10n, 0xFFn
     // String Literals
     /// String Type
     Str,
+    // Template Literals
+    NoSubstitutionTemplate, // `abc`
+    TemplateHead,           // `abc${
+    TemplateMiddle,         // }abc${
+    TemplateTail,           // }abc`
+    // Regular Expression Literals
+    RegExp, // /abc/g
 
     // Other
     Identifier,
@@ -202,13 +223,16 @@ impl Type {
             Yield => "yield",
             Amp => "&",
             Amp2 => "&&",
+            Amp2Eq => "&&=",
             AmpEq => "&=",
+            Arrow => "=>",
             Bang => "!",
             Caret => "^",
             CaretEq => "^=",
             Colon => ":",
             Comma => ",",
             Dot => ".",
+            Dot3 => "...",
             Eq => "=",
             Eq2 => "==",
             Eq3 => "===",
@@ -227,11 +251,15 @@ impl Type {
             PercentEq => "%=",
             Pipe => "|",
             Pipe2 => "||",
+            Pipe2Eq => "||=",
             PipeEq => "|=",
             Plus => "+",
             Plus2 => "++",
             PlusEq => "+=",
             Question => "?",
+            Question2 => "??",
+            Question2Eq => "??=",
+            QuestionDot => "?.",
             RAngle => ">",
             RBrack => "]",
             RCurly => "}",
@@ -246,6 +274,8 @@ impl Type {
             Slash => "/",
             SlashEq => "/=",
             Star => "*",
+            Star2 => "**",
+            Star2Eq => "**=",
             StarEq => "*=",
             Tilde => "~",
             Null => "null",
@@ -253,7 +283,15 @@ impl Type {
             False => "false",
             Decimal => "decimal",
             Hex => "hex",
+            Binary => "binary",
+            Octal => "octal",
+            BigInt => "bigint",
             Str => "string",
+            NoSubstitutionTemplate => "template",
+            TemplateHead => "template head",
+            TemplateMiddle => "template middle",
+            TemplateTail => "template tail",
+            RegExp => "regexp",
             LineTerminator => "lineterminator",
             _ => unreachable!("unreachable token type: {:?}", self),
         }
@@ -335,6 +373,7 @@ impl std::default::Default for Token {
             typ: Type::EOF,
             start: 0,
             end: 0,
+            is_on_new_line: false,
         }
     }
 }
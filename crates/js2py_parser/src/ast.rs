@@ -1,3 +1,4 @@
+use crate::line_index::{LineIndex, Location};
 use crate::syntax::operator::*;
 use serde::Serialize;
 
@@ -19,6 +20,11 @@ impl Span {
     pub fn new(start: usize, end: usize) -> Self {
         Self { start, end }
     }
+
+    /// The locations of the start and the end of the span
+    pub fn to_location(&self, index: &LineIndex) -> (Location, Location) {
+        (index.location(self.start), index.location(self.end))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
@@ -35,15 +41,28 @@ pub enum Statement<'a> {
     ExpressionStatement(Box<ExpressionStatement<'a>>),
     EmptyStatement(Box<EmptyStatement>),
     ReturnStatement(Box<ReturnStatement<'a>>),
+    ThrowStatement(Box<ThrowStatement<'a>>),
+    TryStatement(Box<TryStatement<'a>>),
     ForStatement(Box<ForStatement<'a>>),
+    ForInStatement(Box<ForInStatement<'a>>),
+    ForOfStatement(Box<ForOfStatement<'a>>),
     WhileStatement(Box<WhileStatement<'a>>),
-    BreakStatement(Box<BreakStatement>),
-    ContinueStatement(Box<ContinueStatement>),
+    BreakStatement(Box<BreakStatement<'a>>),
+    ContinueStatement(Box<ContinueStatement<'a>>),
+    LabeledStatement(Box<LabeledStatement<'a>>),
     DoWhileStatement(Box<DoWhileStatement<'a>>),
     SwitchStatement(Box<SwitchStatement<'a>>),
     WithStatement(Box<WithStatement<'a>>),
     VariableDeclarationStatement(Box<VariableDeclaration<'a>>),
     FunctionDeclaration(Box<Function<'a>>),
+    ClassDeclaration(Box<Class<'a>>),
+    /// A statement that failed to parse, only produced in recovering mode
+    ErrorStatement(Box<ErrorStatement>),
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ErrorStatement {
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
@@ -52,6 +71,28 @@ pub struct ReturnStatement<'a> {
     pub argument: Option<Expression<'a>>,
 }
 
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ThrowStatement<'a> {
+    pub span: Span,
+    pub argument: Expression<'a>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct TryStatement<'a> {
+    pub span: Span,
+    pub block: Box<BlockStatement<'a>>,
+    pub handler: Option<Box<CatchClause<'a>>>,
+    pub finalizer: Option<Box<BlockStatement<'a>>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct CatchClause<'a> {
+    pub span: Span,
+    /// The binding of the caught value, `catch { ... }` has none
+    pub param: Option<Identifier<'a>>,
+    pub body: Box<BlockStatement<'a>>,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub struct DoWhileStatement<'a> {
     pub span: Span,
@@ -81,6 +122,22 @@ pub enum ForStatementInit<'a> {
     Expression(Expression<'a>),
 }
 
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ForInStatement<'a> {
+    pub span: Span,
+    pub left: ForStatementLeft<'a>,
+    pub right: Expression<'a>,
+    pub body: Statement<'a>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ForOfStatement<'a> {
+    pub span: Span,
+    pub left: ForStatementLeft<'a>,
+    pub right: Expression<'a>,
+    pub body: Statement<'a>,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub enum ForStatementLeft<'a> {
     VariableDeclaration(Box<VariableDeclaration<'a>>),
@@ -88,13 +145,28 @@ pub enum ForStatementLeft<'a> {
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
-pub struct ContinueStatement {
+pub struct ContinueStatement<'a> {
+    pub span: Span,
+    pub label: Option<LabelIdentifier<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct BreakStatement<'a> {
+    pub span: Span,
+    pub label: Option<LabelIdentifier<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct LabeledStatement<'a> {
     pub span: Span,
+    pub label: LabelIdentifier<'a>,
+    pub body: Statement<'a>,
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
-pub struct BreakStatement {
+pub struct LabelIdentifier<'a> {
     pub span: Span,
+    pub name: &'a str,
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
@@ -147,10 +219,63 @@ pub struct IfStatement<'a> {
 pub struct VariableDeclaration<'a> {
     pub span: Span,
     pub kind: VariableDeclarationKind,
-    pub id: Identifier<'a>,
+    pub declarations: Vec<VariableDeclarator<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct VariableDeclarator<'a> {
+    pub span: Span,
+    pub id: BindingPattern<'a>,
     pub init: Option<Expression<'a>>,
 }
 
+/// The target of a declaration, an identifier or a destructuring pattern
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub enum BindingPattern<'a> {
+    BindingIdentifier(Box<Identifier<'a>>),
+    ObjectPattern(Box<ObjectPattern<'a>>),
+    ArrayPattern(Box<ArrayPattern<'a>>),
+    /// A pattern with a default value, e.g. `a = 1` in `let [a = 1] = b`
+    AssignmentPattern(Box<AssignmentPattern<'a>>),
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ObjectPattern<'a> {
+    pub span: Span,
+    pub properties: Vec<BindingProperty<'a>>,
+    pub rest: Option<Box<RestElement<'a>>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct BindingProperty<'a> {
+    pub span: Span,
+    pub key: PropertyKey<'a>,
+    pub value: BindingPattern<'a>,
+    /// `{ a }` or `{ a = 1 }`, the key is also the bound identifier
+    pub shorthand: bool,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ArrayPattern<'a> {
+    pub span: Span,
+    /// `None` for a hole, e.g. `[, a]`
+    pub elements: Vec<Option<BindingPattern<'a>>>,
+    pub rest: Option<Box<RestElement<'a>>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct AssignmentPattern<'a> {
+    pub span: Span,
+    pub left: BindingPattern<'a>,
+    pub right: Expression<'a>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct RestElement<'a> {
+    pub span: Span,
+    pub argument: BindingPattern<'a>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub enum VariableDeclarationKind {
     Var,
@@ -166,6 +291,15 @@ pub struct Function<'a> {
     pub body: Option<Box<FunctionBody<'a>>>,
 }
 
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ArrowFunctionExpression<'a> {
+    pub span: Span,
+    /// The body is a single expression, stored as the only statement of `body`
+    pub expression: bool,
+    pub params: Box<FormalParameters<'a>>,
+    pub body: Box<FunctionBody<'a>>,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub struct FormalParameters<'a> {
     pub span: Span,
@@ -178,24 +312,83 @@ pub struct FunctionBody<'a> {
     pub statements: Vec<Statement<'a>>,
 }
 
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct Class<'a> {
+    pub span: Span,
+    pub id: Option<Identifier<'a>>,
+    pub super_class: Option<Expression<'a>>,
+    pub body: Vec<ClassElement<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub enum ClassElement<'a> {
+    MethodDefinition(Box<MethodDefinition<'a>>),
+    PropertyDefinition(Box<PropertyDefinition<'a>>),
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct MethodDefinition<'a> {
+    pub span: Span,
+    pub key: PropertyKey<'a>,
+    pub value: Box<Function<'a>>,
+    pub kind: MethodDefinitionKind,
+    pub is_static: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MethodDefinitionKind {
+    Constructor,
+    Method,
+    Get,
+    Set,
+}
+
+/// A class field, `x = 1;` or `static x;`
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct PropertyDefinition<'a> {
+    pub span: Span,
+    pub key: PropertyKey<'a>,
+    pub value: Option<Expression<'a>>,
+    pub is_static: bool,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub enum Expression<'a> {
     BooleanLiteral(Box<BooleanLiteral>),
     NullLiteral(Box<NullLiteral>),
     NumericLiteral(Box<NumericLiteral<'a>>),
     StringLiteral(Box<StringLiteral<'a>>),
+    TemplateLiteral(Box<TemplateLiteral<'a>>),
+    RegExpLiteral(Box<RegExpLiteral<'a>>),
     Identifier(Box<Identifier<'a>>),
     SequenceExpression(Box<SequenceExpression<'a>>),
     BinaryExpression(Box<BinaryExpression<'a>>),
     UnaryExpression(Box<UnaryExpression<'a>>),
+    UpdateExpression(Box<UpdateExpression<'a>>),
     LogicalExpression(Box<LogicalExpression<'a>>),
+    ConditionalExpression(Box<ConditionalExpression<'a>>),
     AssignmentExpression(Box<AssignmentExpression<'a>>),
     ArrayExpression(Box<ArrayExpression<'a>>),
     ObjectExpression(Box<ObjectExpression<'a>>),
     StaticMemberExpression(Box<StaticMemberExpression<'a>>),
     ComputedMemberExpression(Box<ComputedMemberExpression<'a>>),
     CallExpression(Box<CallExpression<'a>>),
+    NewExpression(Box<NewExpression<'a>>),
+    MetaProperty(Box<MetaProperty<'a>>),
+    ChainExpression(Box<ChainExpression<'a>>),
+    ArrowFunctionExpression(Box<ArrowFunctionExpression<'a>>),
+    FunctionExpression(Box<Function<'a>>),
+    ClassExpression(Box<Class<'a>>),
+    ThisExpression(Box<ThisExpression>),
+    Super(Box<Super>),
     ParenthesizedExpression(Box<ParenthesizedExpression<'a>>),
+    /// An expression that failed to parse, only produced in recovering mode
+    ErrorExpression(Box<ErrorExpression>),
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ErrorExpression {
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
@@ -258,11 +451,22 @@ pub struct UnaryExpression<'a> {
     pub argument: Expression<'a>,
 }
 
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct UpdateExpression<'a> {
+    pub span: Span,
+    pub operator: UpdateOperator,
+    /// `++a` rather than `a++`
+    pub prefix: bool,
+    pub argument: Expression<'a>,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub struct StaticMemberExpression<'a> {
     pub span: Span,
     pub object: Expression<'a>,
     pub property: IdentifierName<'a>,
+    /// `a?.b`
+    pub optional: bool,
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
@@ -270,6 +474,8 @@ pub struct ComputedMemberExpression<'a> {
     pub span: Span,
     pub object: Expression<'a>,
     pub expression: Expression<'a>,
+    /// `a?.[b]`
+    pub optional: bool,
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
@@ -277,6 +483,32 @@ pub struct CallExpression<'a> {
     pub span: Span,
     pub callee: Expression<'a>,
     pub arguments: Vec<Expression<'a>>,
+    /// `f?.()`
+    pub optional: bool,
+}
+
+/// `new C(...)`, the arguments are empty for `new C`
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct NewExpression<'a> {
+    pub span: Span,
+    pub callee: Expression<'a>,
+    pub arguments: Vec<Expression<'a>>,
+}
+
+/// `new.target`
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct MetaProperty<'a> {
+    pub span: Span,
+    pub meta: IdentifierName<'a>,
+    pub property: IdentifierName<'a>,
+}
+
+/// A chain of member accesses and calls with at least one `?.`, the whole chain is `undefined`
+/// when the object of a `?.` is nullish
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ChainExpression<'a> {
+    pub span: Span,
+    pub expression: Expression<'a>,
 }
 
 #[derive(Debug, Clone, Serialize, GetSpan)]
@@ -294,6 +526,15 @@ pub enum AssignmentTarget<'a> {
     ComputedMemberExpression(Box<ComputedMemberExpression<'a>>),
 }
 
+/// `test ? consequent : alternate`
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ConditionalExpression<'a> {
+    pub span: Span,
+    pub test: Expression<'a>,
+    pub consequent: Expression<'a>,
+    pub alternate: Expression<'a>,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub struct LogicalExpression<'a> {
     pub span: Span,
@@ -308,6 +549,17 @@ pub struct ParenthesizedExpression<'a> {
     pub expression: Expression<'a>,
 }
 
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct ThisExpression {
+    pub span: Span,
+}
+
+/// `super` in `super(...)` or `super.method(...)`
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct Super {
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub struct BooleanLiteral {
     pub span: Span,
@@ -332,6 +584,30 @@ pub struct StringLiteral<'a> {
     pub value: &'a str,
 }
 
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct RegExpLiteral<'a> {
+    pub span: Span,
+    /// The source text between the slashes
+    pub pattern: &'a str,
+    pub flags: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct TemplateLiteral<'a> {
+    pub span: Span,
+    /// The string parts, there is always one more quasi than expressions
+    pub quasis: Vec<TemplateElement<'a>>,
+    pub expressions: Vec<Expression<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize, GetSpan)]
+pub struct TemplateElement<'a> {
+    pub span: Span,
+    /// The source text between the delimiters, escape sequences are not processed
+    pub raw: &'a str,
+    pub tail: bool,
+}
+
 #[derive(Debug, Clone, Serialize, GetSpan)]
 pub struct Identifier<'a> {
     pub span: Span,
@@ -0,0 +1,130 @@
+use super::*;
+use crate::ast::*;
+
+impl<'a> Parser<'a> {
+    /// BindingPattern: an identifier, an object pattern or an array pattern
+    pub(crate) fn parse_binding_pattern(&mut self) -> Result<BindingPattern<'a>> {
+        match self.cur_kind() {
+            Type::LCurly => self.parse_object_pattern(),
+            Type::LBrack => self.parse_array_pattern(),
+            _ => Ok(BindingPattern::BindingIdentifier(Box::new(
+                self.parse_identifier()?,
+            ))),
+        }
+    }
+
+    /// BindingElement: a binding pattern with an optional default value
+    fn parse_binding_element(&mut self) -> Result<BindingPattern<'a>> {
+        let span = self.start_span();
+        let pattern = self.parse_binding_pattern()?;
+        self.parse_binding_default(span, pattern)
+    }
+
+    fn parse_binding_default(
+        &mut self,
+        span: Span,
+        left: BindingPattern<'a>,
+    ) -> Result<BindingPattern<'a>> {
+        if !self.eat(Type::Eq) {
+            return Ok(left);
+        }
+        let right = self.with_in(true, Self::parse_assignment_expression_or_higher)?;
+        Ok(BindingPattern::AssignmentPattern(Box::new(
+            AssignmentPattern {
+                span: self.end_span(span),
+                left,
+                right,
+            },
+        )))
+    }
+
+    /// `...pattern`, which must be the last element of the enclosing pattern
+    fn parse_rest_element(&mut self) -> Result<Box<RestElement<'a>>> {
+        let span = self.start_span();
+        self.bump_any(); // bump `...`
+        let argument = self.parse_binding_pattern()?;
+        Ok(Box::new(RestElement {
+            span: self.end_span(span),
+            argument,
+        }))
+    }
+
+    fn parse_object_pattern(&mut self) -> Result<BindingPattern<'a>> {
+        let span = self.start_span();
+        self.expect(Type::LCurly)?;
+        let mut properties = vec![];
+        let mut rest = None;
+        while !self.at(Type::RCurly) {
+            if self.at(Type::Dot3) {
+                rest = Some(self.parse_rest_element()?);
+                break;
+            }
+            properties.push(self.parse_binding_property()?);
+            if !self.at(Type::RCurly) {
+                self.expect(Type::Comma)?;
+            }
+        }
+        self.expect(Type::RCurly)?;
+        Ok(BindingPattern::ObjectPattern(Box::new(ObjectPattern {
+            span: self.end_span(span),
+            properties,
+            rest,
+        })))
+    }
+
+    fn parse_binding_property(&mut self) -> Result<BindingProperty<'a>> {
+        let span = self.start_span();
+        let is_identifier = self.at(Type::Identifier);
+        let key = self.parse_property_key()?;
+        let shorthand = !self.eat(Type::Colon);
+        let value = if shorthand {
+            // `{ a }` and `{ a = 1 }` bind the key itself
+            let PropertyKey::IdentifierName(name) = &key else {
+                return Err(self.unexpected());
+            };
+            if !is_identifier {
+                return Err(self.unexpected());
+            }
+            let id = BindingPattern::BindingIdentifier(Box::new(Identifier {
+                span: name.span,
+                name: name.name,
+            }));
+            self.parse_binding_default(span, id)?
+        } else {
+            self.parse_binding_element()?
+        };
+        Ok(BindingProperty {
+            span: self.end_span(span),
+            key,
+            value,
+            shorthand,
+        })
+    }
+
+    fn parse_array_pattern(&mut self) -> Result<BindingPattern<'a>> {
+        let span = self.start_span();
+        self.expect(Type::LBrack)?;
+        let mut elements = vec![];
+        let mut rest = None;
+        while !self.at(Type::RBrack) {
+            if self.eat(Type::Comma) {
+                elements.push(None);
+                continue;
+            }
+            if self.at(Type::Dot3) {
+                rest = Some(self.parse_rest_element()?);
+                break;
+            }
+            elements.push(Some(self.parse_binding_element()?));
+            if !self.at(Type::RBrack) {
+                self.expect(Type::Comma)?;
+            }
+        }
+        self.expect(Type::RBrack)?;
+        Ok(BindingPattern::ArrayPattern(Box::new(ArrayPattern {
+            span: self.end_span(span),
+            elements,
+            rest,
+        })))
+    }
+}
@@ -0,0 +1,109 @@
+use super::*;
+use ast::*;
+
+impl<'a> Parser<'a> {
+    pub(crate) fn parse_class_declaration(&mut self) -> Result<Statement<'a>> {
+        let class = self.parse_class()?;
+        Ok(Statement::ClassDeclaration(class))
+    }
+
+    pub(crate) fn parse_class_expression(&mut self) -> Result<Expression<'a>> {
+        let class = self.parse_class()?;
+        Ok(Expression::ClassExpression(class))
+    }
+
+    fn parse_class(&mut self) -> Result<Box<Class<'a>>> {
+        let span = self.start_span();
+        self.expect(Type::Class)?;
+        let id = self
+            .at(Type::Identifier)
+            .then(|| self.parse_identifier())
+            .transpose()?;
+        let super_class = self
+            .eat(Type::Extends)
+            .then(|| self.parse_lhs_expression_or_higher())
+            .transpose()?;
+        let body = self.parse_normal_list(Type::LCurly, Type::RCurly, Self::parse_class_element)?;
+        Ok(Box::new(Class {
+            span: self.end_span(span),
+            id,
+            super_class,
+            body,
+        }))
+    }
+
+    fn parse_class_element(&mut self) -> Result<Option<ClassElement<'a>>> {
+        while self.eat(Type::Semicolon) {}
+        if self.at(Type::RCurly) {
+            return Ok(None);
+        }
+        let span = self.start_span();
+
+        // `static`, `get` and `set` are modifiers unless they are the name of the member
+        let mut key = None;
+        let mut is_static = false;
+        if self.at(Type::Static) {
+            key = self.parse_class_element_modifier();
+            is_static = key.is_none();
+        }
+        let mut kind = MethodDefinitionKind::Method;
+        if key.is_none() && matches!(self.cur_kind(), Type::Get | Type::Set) {
+            let modifier = match self.cur_kind() {
+                Type::Get => MethodDefinitionKind::Get,
+                _ => MethodDefinitionKind::Set,
+            };
+            key = self.parse_class_element_modifier();
+            if key.is_none() {
+                kind = modifier;
+            }
+        }
+        let key = match key {
+            Some(key) => key,
+            None => self.parse_property_key()?,
+        };
+
+        if !self.at(Type::LParen) && kind == MethodDefinitionKind::Method {
+            let value = self
+                .eat(Type::Eq)
+                .then(|| self.parse_assignment_expression_or_higher())
+                .transpose()?;
+            self.auto_semicoclon_insertion()?;
+            return Ok(Some(ClassElement::PropertyDefinition(Box::new(
+                PropertyDefinition {
+                    span: self.end_span(span),
+                    key,
+                    value,
+                    is_static,
+                },
+            ))));
+        }
+
+        let function_span = self.start_span();
+        let value = self.parse_function(function_span, None)?;
+        let is_constructor =
+            matches!(&key, PropertyKey::IdentifierName(id) if id.name == "constructor");
+        if is_constructor && !is_static && kind == MethodDefinitionKind::Method {
+            kind = MethodDefinitionKind::Constructor;
+        }
+        Ok(Some(ClassElement::MethodDefinition(Box::new(
+            MethodDefinition {
+                span: self.end_span(span),
+                key,
+                value,
+                kind,
+                is_static,
+            },
+        ))))
+    }
+
+    /// Parse a modifier of a class member, returns it as a key if it is the name of the member
+    fn parse_class_element_modifier(&mut self) -> Option<PropertyKey<'a>> {
+        let (span, name) = self.parse_identifier_kind();
+        // `static() {}`, `get = 1` or `set;`
+        matches!(
+            self.cur_kind(),
+            Type::LParen | Type::Eq | Type::Semicolon | Type::RCurly
+        )
+        .then_some(PropertyKey::IdentifierName(IdentifierName { span, name }))
+    }
+}
@@ -0,0 +1,259 @@
+//! Errors reported by the parser and their rendering as diagnostics.
+
+use crate::ast::Span;
+use crate::line_index::LineIndex;
+use crate::syntax::lex::TokenTypeUtil;
+use js2py_lexer::error::{LexError, LexErrorKind};
+use js2py_lexer::token::Type;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// `found` is not allowed here, `expected` is the token that would have been
+    UnexpectedToken {
+        expected: Option<Type>,
+        found: Type,
+        span: Span,
+    },
+    InvalidAssignmentTarget {
+        span: Span,
+    },
+    /// An expression in the parameter list of an arrow function that is not a parameter
+    InvalidParameter {
+        span: Span,
+    },
+    /// A statement is not terminated by a semicolon and none can be inserted
+    MissingSemicolon {
+        span: Span,
+    },
+    EmptyParenthesizedExpression {
+        span: Span,
+    },
+    InvalidNumber {
+        span: Span,
+    },
+    /// A line terminator between `throw` and its expression, where ASI would leave it empty
+    NewLineAfterThrow {
+        span: Span,
+    },
+    /// A `try` block followed by neither a `catch` nor a `finally` block
+    MissingCatchOrFinally {
+        span: Span,
+    },
+    /// A `break` or `continue` to a label that does not enclose it
+    UndefinedLabel {
+        label: String,
+        span: Span,
+    },
+    /// A `continue` to a label of a statement that is not a loop
+    InvalidContinueLabel {
+        label: String,
+        span: Span,
+    },
+    /// A destructuring pattern or a `const` declaration without an initializer
+    MissingInitializer {
+        span: Span,
+    },
+    /// A unary expression as the left operand of `**`, e.g. `-a ** b`
+    UnaryBeforeExponentiation {
+        span: Span,
+    },
+    /// An optional chain as the constructor of `new`, e.g. `new a?.b()`
+    OptionalChainInNew {
+        span: Span,
+    },
+    LexError(LexError),
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnexpectedToken { span, .. }
+            | Self::InvalidAssignmentTarget { span }
+            | Self::InvalidParameter { span }
+            | Self::MissingSemicolon { span }
+            | Self::EmptyParenthesizedExpression { span }
+            | Self::InvalidNumber { span }
+            | Self::NewLineAfterThrow { span }
+            | Self::MissingCatchOrFinally { span }
+            | Self::UndefinedLabel { span, .. }
+            | Self::InvalidContinueLabel { span, .. }
+            | Self::MissingInitializer { span }
+            | Self::UnaryBeforeExponentiation { span }
+            | Self::OptionalChainInNew { span } => *span,
+            Self::LexError(error) => Span::new(error.start, error.end),
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnexpectedToken {
+                expected: Some(expected),
+                found,
+                ..
+            } => format!(
+                "Expected {} but found {}",
+                describe_token(*expected),
+                describe_token(*found)
+            ),
+            Self::UnexpectedToken { found, .. } => {
+                format!("Unexpected {}", describe_token(*found))
+            }
+            Self::InvalidAssignmentTarget { .. } => String::from("Invalid assignment target"),
+            Self::InvalidParameter { .. } => String::from("Invalid parameter"),
+            Self::MissingSemicolon { .. } => String::from(
+                "Expected a semicolon or an implicit semicolon after a statement, but found none",
+            ),
+            Self::EmptyParenthesizedExpression { .. } => {
+                String::from("Parenthesized expression must contain at least one expression")
+            }
+            Self::InvalidNumber { .. } => String::from("Invalid numeric literal"),
+            Self::NewLineAfterThrow { .. } => String::from("Illegal newline after throw"),
+            Self::MissingCatchOrFinally { .. } => {
+                String::from("Missing catch or finally after try")
+            }
+            Self::UndefinedLabel { label, .. } => format!("Undefined label `{}`", label),
+            Self::InvalidContinueLabel { label, .. } => {
+                format!("Label `{}` does not denote a loop", label)
+            }
+            Self::MissingInitializer { .. } => String::from("Missing initializer in declaration"),
+            Self::UnaryBeforeExponentiation { .. } => {
+                String::from("Unary operator used immediately before exponentiation expression")
+            }
+            Self::OptionalChainInNew { .. } => {
+                String::from("Invalid optional chain from new expression")
+            }
+            Self::LexError(error) => error.message.clone(),
+        }
+    }
+
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            Self::UnexpectedToken { .. } => None,
+            Self::InvalidAssignmentTarget { .. } => {
+                Some("only identifiers and member expressions can be assigned to")
+            }
+            Self::InvalidParameter { .. } => Some("only identifiers can be used as parameters"),
+            Self::MissingSemicolon { .. } => Some("add a `;` at the end of the statement"),
+            Self::EmptyParenthesizedExpression { .. } => {
+                Some("add an expression between the parentheses")
+            }
+            Self::InvalidNumber { .. } => None,
+            Self::NewLineAfterThrow { .. } => {
+                Some("start the thrown expression on the same line as `throw`")
+            }
+            Self::MissingCatchOrFinally { .. } => Some("add a `catch` or a `finally` block"),
+            Self::UndefinedLabel { .. } => {
+                Some("a label can only be used in the statement it labels")
+            }
+            Self::InvalidContinueLabel { .. } => Some("use `break` to leave a labeled block"),
+            Self::MissingInitializer { .. } => Some("add `= value` after the declared name"),
+            Self::UnaryBeforeExponentiation { .. } => {
+                Some("wrap the unary expression or the exponentiation in parentheses")
+            }
+            Self::OptionalChainInNew { .. } => Some("wrap the optional chain in parentheses"),
+            Self::LexError(error) => match error.kind {
+                LexErrorKind::UnterminatedString => Some("add the closing quote"),
+                LexErrorKind::UnterminatedTemplate => Some("add the closing backtick"),
+                LexErrorKind::UnterminatedRegExp => Some("add the closing slash"),
+                LexErrorKind::UnterminatedComment => Some("add the closing `*/`"),
+                LexErrorKind::OctalLiteral => Some("use `\\x` or `\\u` escapes instead"),
+                LexErrorKind::BadEscape
+                | LexErrorKind::InvalidNumber
+                | LexErrorKind::UnexpectedCharacter => None,
+            },
+        }
+    }
+
+    /// Render the error with the offending source line and a caret underline:
+    ///
+    /// ```text
+    /// error: Expected `)` but found `;`
+    ///  --> input.js:1:8
+    ///   |
+    /// 1 | f(a, b;
+    ///   |       ^
+    /// ```
+    ///
+    /// `index` is the [`LineIndex`] of `source`, so that it is built once for all the errors.
+    pub fn render(&self, source: &str, index: &LineIndex, filename: &str) -> String {
+        let span = self.span();
+        let (start, end) = span.to_location(index);
+
+        let line_start = index.line_start(start.line).unwrap_or_default();
+        let line_end = index
+            .line_start(start.line + 1)
+            .unwrap_or(source.len())
+            .max(line_start);
+        let line =
+            source[line_start..line_end].trim_end_matches(['\n', '\r', '\u{2028}', '\u{2029}']);
+
+        // columns are counted in characters, so that the caret lines up in a terminal
+        let caret_start = line[..span.start - line_start].chars().count();
+        let caret_len = if end.line == start.line {
+            line[span.start - line_start..(span.end - line_start).min(line.len())]
+                .chars()
+                .count()
+        } else {
+            line[span.start - line_start..].chars().count()
+        };
+
+        let gutter = start.line.to_string().len();
+        let mut out = format!("error: {}\n", self.message());
+        out += &format!(
+            "{:gutter$}--> {}:{}\n",
+            "",
+            filename,
+            start,
+            gutter = gutter
+        );
+        out += &format!("{:gutter$} |\n", "", gutter = gutter);
+        out += &format!("{} | {}\n", start.line, line);
+        out += &format!(
+            "{:gutter$} | {:caret_start$}{}",
+            "",
+            "",
+            "^".repeat(caret_len.max(1)),
+            gutter = gutter,
+            caret_start = caret_start
+        );
+        if let Some(help) = self.help() {
+            out += &format!("\n{:gutter$} |\n", "", gutter = gutter);
+            out += &format!("{:gutter$} = help: {}", "", help, gutter = gutter);
+        }
+        out
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<LexError> for ParseError {
+    fn from(error: LexError) -> Self {
+        Self::LexError(error)
+    }
+}
+
+fn describe_token(kind: Type) -> String {
+    if kind.is_eof() {
+        return String::from("end of input");
+    }
+    if kind.is_literal()
+        || matches!(
+            kind,
+            Type::Identifier
+                | Type::NoSubstitutionTemplate
+                | Type::TemplateHead
+                | Type::TemplateMiddle
+                | Type::TemplateTail
+        )
+    {
+        return kind.to_str().to_string();
+    }
+    format!("`{}`", kind.to_str())
+}
@@ -1,12 +1,12 @@
 use op::*;
 use syntax::lex::TokenTypeUtil;
+use syntax::number::parse_number;
 use syntax::precedence::Precedence;
 
 use super::*;
 use ast::*;
 
 #[allow(dead_code)]
-
 impl<'a> Parser<'a> {
     pub(crate) fn parse_expr(&mut self) -> Result<Expression<'a>> {
         let span = self.start_span();
@@ -22,7 +22,21 @@ impl<'a> Parser<'a> {
 
     pub(crate) fn parse_paren_expression(&mut self) -> Result<Expression<'a>> {
         self.expect(Type::LParen)?;
-        let expression = self.parse_expr()?;
+        let span = self.start_span();
+        let expression = match self.parse_expr() {
+            Ok(expression) => expression,
+            // keep the enclosing statement if the error is contained in the parentheses
+            Err(error) if self.recovering => {
+                if !self.skip_parenthesized() {
+                    return Err(error);
+                }
+                self.errors.push(error);
+                Expression::ErrorExpression(Box::new(ErrorExpression {
+                    span: self.recovered_span(span),
+                }))
+            }
+            Err(error) => return Err(error),
+        };
         self.expect(Type::RParen)?;
         Ok(expression)
     }
@@ -47,7 +61,7 @@ impl<'a> Parser<'a> {
 
     pub(crate) fn parse_assignment_expression_or_higher(&mut self) -> Result<Expression<'a>> {
         let span = self.start_span();
-        let lhs = self.parse_binary_expression_or_higher(Precedence::Comma)?;
+        let lhs = self.parse_conditional_expression(span)?;
         let kind = self.cur_kind();
         if kind.is_assignment_operator() {
             return self.parse_assignment_expression_recursive(span, lhs);
@@ -56,6 +70,28 @@ impl<'a> Parser<'a> {
         Ok(lhs)
     }
 
+    /// Section 13.14 Conditional Operator
+    ///
+    /// Both branches are assignment expressions, so a conditional in the alternate nests to the
+    /// right. `in` is a relational operator in the consequent, which is delimited by `:`.
+    fn parse_conditional_expression(&mut self, span: Span) -> Result<Expression<'a>> {
+        let test = self.parse_binary_expression_or_higher(Precedence::Comma)?;
+        if !self.eat(Type::Question) {
+            return Ok(test);
+        }
+        let consequent = self.with_in(true, Self::parse_assignment_expression_or_higher)?;
+        self.expect(Type::Colon)?;
+        let alternate = self.parse_assignment_expression_or_higher()?;
+        Ok(Expression::ConditionalExpression(Box::new(
+            ConditionalExpression {
+                span: self.end_span(span),
+                test,
+                consequent,
+                alternate,
+            },
+        )))
+    }
+
     fn parse_assignment_expression_recursive(
         &mut self,
         span: Span,
@@ -70,7 +106,7 @@ impl<'a> Parser<'a> {
             Expression::ComputedMemberExpression(member) => {
                 AssignmentTarget::ComputedMemberExpression(member)
             }
-            _ => return Err(format!("Invalid assignment target: {:?}", lhs)),
+            _ => return Err(ParseError::InvalidAssignmentTarget { span: lhs.span() }),
         };
         self.bump_any();
         let right = self.parse_assignment_expression_or_higher()?;
@@ -107,31 +143,63 @@ impl<'a> Parser<'a> {
     }
 
     /// Section 13.4 Update Expression
-    #[allow(unused_variables)]
     fn parse_update_expression(&mut self, lhs_span: Span) -> Result<Expression<'a>> {
-        // TODO: support prefix update expressions
-
-        // let kind = self.cur_kind();
-        // // ++ -- prefix update expressions
-        // if kind.is_update_operator() {
-        //     ...
-        // }
+        // ++ -- prefix update expressions
+        if matches!(self.cur_kind(), Type::Plus2 | Type::Minus2) {
+            let operator = map_update_operator(self.cur_kind());
+            self.bump_any();
+            let argument_span = self.start_span();
+            let argument = self.parse_simple_unary_expression(argument_span)?;
+            Self::check_update_target(&argument)?;
+            return Ok(Expression::UpdateExpression(Box::new(UpdateExpression {
+                span: self.end_span(lhs_span),
+                operator,
+                prefix: true,
+                argument,
+            })));
+        }
 
-        // let span = self.start_span();
         let lhs = self.parse_lhs_expression_or_higher()?;
-        // ++ -- postfix update expressions
-        // if self.cur_kind().is_update_operator() && !self.cur_token().is_on_new_line {
-        //     ...
-        // }
+        // ++ -- postfix update expressions, restricted production: no line terminator before
+        if matches!(self.cur_kind(), Type::Plus2 | Type::Minus2) && !self.cur_token().is_on_new_line
+        {
+            Self::check_update_target(&lhs)?;
+            let operator = map_update_operator(self.cur_kind());
+            self.bump_any();
+            return Ok(Expression::UpdateExpression(Box::new(UpdateExpression {
+                span: self.end_span(lhs_span),
+                operator,
+                prefix: false,
+                argument: lhs,
+            })));
+        }
         Ok(lhs)
     }
 
+    /// The operand of `++` and `--` is assigned to, like the left side of `+=`
+    fn check_update_target(argument: &Expression<'a>) -> Result<()> {
+        match argument {
+            Expression::Identifier(_)
+            | Expression::StaticMemberExpression(_)
+            | Expression::ComputedMemberExpression(_) => Ok(()),
+            _ => Err(ParseError::InvalidAssignmentTarget {
+                span: argument.span(),
+            }),
+        }
+    }
+
     /// Section 13.3 Left-Hand-Side Expression
     pub(crate) fn parse_lhs_expression_or_higher(&mut self) -> Result<Expression<'a>> {
         let span = self.start_span();
         let lhs = self.parse_member_expression_or_higher()?;
         let lhs = self.parse_call_expression_rest(span, lhs)?;
-        Ok(lhs)
+        if !is_optional_chain(&lhs) {
+            return Ok(lhs);
+        }
+        Ok(Expression::ChainExpression(Box::new(ChainExpression {
+            span: self.end_span(span),
+            expression: lhs,
+        })))
     }
 
     /// Section 13.3 Call Expression
@@ -145,7 +213,7 @@ impl<'a> Parser<'a> {
             lhs = self.parse_member_expression_rest(lhs_span, lhs)?;
 
             if self.at(Type::LParen) {
-                lhs = self.parse_call_arguments(lhs_span, lhs)?;
+                lhs = self.parse_call_arguments(lhs_span, lhs, false)?;
                 continue;
             }
             break;
@@ -157,31 +225,85 @@ impl<'a> Parser<'a> {
         &mut self,
         lhs_span: Span,
         callee: Expression<'a>,
+        optional: bool,
     ) -> Result<Expression<'a>> {
+        let arguments = self.parse_arguments()?;
+        Ok(Expression::CallExpression(Box::new(CallExpression {
+            span: self.end_span(lhs_span),
+            callee,
+            arguments,
+            optional,
+        })))
+    }
+
+    /// `(a, b)` after a callee
+    fn parse_arguments(&mut self) -> Result<Vec<Expression<'a>>> {
         self.expect(Type::LParen)?;
         let mut arguments = Vec::new();
         while !self.at(Type::RParen) {
-            let argument = self.parse_assignment_expression_or_higher()?;
+            let argument = self.with_in(true, Self::parse_assignment_expression_or_higher)?;
             arguments.push(argument);
             if self.at(Type::Comma) {
                 self.bump_any();
             }
         }
         self.expect(Type::RParen)?;
-        Ok(Expression::CallExpression(Box::new(CallExpression {
-            span: self.end_span(lhs_span),
-            callee,
-            arguments,
-        })))
+        Ok(arguments)
     }
 
     /// Section 13.3 Member Expression
     fn parse_member_expression_or_higher(&mut self) -> Result<Expression<'a>> {
         let span = self.start_span();
-        let lhs = self.parse_primary_expression()?;
+        let lhs = if self.at(Type::New) {
+            self.parse_new_expression()?
+        } else {
+            self.parse_primary_expression()?
+        };
         self.parse_member_expression_rest(span, lhs)
     }
 
+    /// `new C(...)`, `new C` and `new.target`
+    ///
+    /// The callee is a member expression, so the first arguments belong to `new`: `new a.b()`
+    /// constructs `a.b`, and `new f()()` calls the constructed object.
+    fn parse_new_expression(&mut self) -> Result<Expression<'a>> {
+        let span = self.start_span();
+        let (meta_span, meta) = self.parse_identifier_kind(); // bump `new`
+        if self.eat(Type::Dot) {
+            if !self.at(Type::Target) {
+                return Err(self.unexpected());
+            }
+            let (property_span, property) = self.parse_identifier_kind();
+            return Ok(Expression::MetaProperty(Box::new(MetaProperty {
+                span: self.end_span(span),
+                meta: IdentifierName {
+                    span: meta_span,
+                    name: meta,
+                },
+                property: IdentifierName {
+                    span: property_span,
+                    name: property,
+                },
+            })));
+        }
+        let callee = self.parse_member_expression_or_higher()?;
+        if is_optional_chain(&callee) {
+            return Err(ParseError::OptionalChainInNew {
+                span: self.end_span(span),
+            });
+        }
+        let arguments = if self.at(Type::LParen) {
+            self.parse_arguments()?
+        } else {
+            vec![]
+        };
+        Ok(Expression::NewExpression(Box::new(NewExpression {
+            span: self.end_span(span),
+            callee,
+            arguments,
+        })))
+    }
+
     /// parse rhs of a member expression, starting from lhs
     #[allow(unused_variables)]
     fn parse_member_expression_rest(
@@ -192,26 +314,42 @@ impl<'a> Parser<'a> {
         let mut lhs = lhs;
         loop {
             lhs = match self.cur_kind() {
-                Type::Dot => self.parse_static_member_expression(lhs_span, lhs)?,
-                Type::LBrack => self.parse_computed_member_expression(lhs_span, lhs)?,
+                Type::Dot => {
+                    self.bump_any(); // advance `.`
+                    self.parse_static_member_expression(lhs_span, lhs, false)?
+                }
+                Type::LBrack => self.parse_computed_member_expression(lhs_span, lhs, false)?,
+                // `a?.b`, `a?.[b]` and `a?.(b)`
+                Type::QuestionDot => {
+                    self.bump_any(); // advance `?.`
+                    match self.cur_kind() {
+                        Type::LBrack => {
+                            self.parse_computed_member_expression(lhs_span, lhs, true)?
+                        }
+                        Type::LParen => self.parse_call_arguments(lhs_span, lhs, true)?,
+                        _ => self.parse_static_member_expression(lhs_span, lhs, true)?,
+                    }
+                }
                 _ => break,
             };
         }
         Ok(lhs)
     }
 
+    /// The property after `.` or `?.`
     fn parse_static_member_expression(
         &mut self,
         lhs_span: Span,
         lhs: Expression<'a>,
+        optional: bool,
     ) -> Result<Expression<'a>> {
-        self.bump_any(); // advance `.`
         let ident = self.parse_identifier_name()?;
         Ok(Expression::StaticMemberExpression(Box::new(
             StaticMemberExpression {
                 span: self.end_span(lhs_span),
                 object: lhs,
                 property: ident,
+                optional,
             },
         )))
     }
@@ -220,15 +358,17 @@ impl<'a> Parser<'a> {
         &mut self,
         lhs_span: Span,
         lhs: Expression<'a>,
+        optional: bool,
     ) -> Result<Expression<'a>> {
         self.bump_any(); // advance `[`
-        let property = self.parse_expr()?;
+        let property = self.with_in(true, Self::parse_expr)?;
         self.expect(Type::RBrack)?;
         Ok(Expression::ComputedMemberExpression(Box::new(
             ComputedMemberExpression {
                 span: self.end_span(lhs_span),
                 object: lhs,
                 expression: property,
+                optional,
             },
         )))
     }
@@ -238,11 +378,33 @@ impl<'a> Parser<'a> {
         let span = self.start_span();
 
         match self.cur_kind() {
-            Type::Identifier => self.parse_identifier_expression(),
+            Type::Identifier => {
+                let ident = self.parse_identifier_expression()?;
+                if self.at_arrow() {
+                    let params_span = self.end_span(span);
+                    return self.parse_arrow_function_expression(span, params_span, vec![ident]);
+                }
+                Ok(ident)
+            }
             kind if kind.is_literal() => self.parse_literal_expression(),
             Type::LBrack => self.parse_array_expression(),
             Type::LCurly => self.parse_object_expression(),
             Type::LParen => self.parse_parenthesized_expression(span),
+            Type::Function => self.parse_function_expression(),
+            Type::Class => self.parse_class_expression(),
+            Type::This => {
+                self.bump_any();
+                Ok(Expression::ThisExpression(Box::new(ThisExpression {
+                    span: self.end_span(span),
+                })))
+            }
+            Type::Super => {
+                self.bump_any();
+                Ok(Expression::Super(Box::new(Super {
+                    span: self.end_span(span),
+                })))
+            }
+            Type::NoSubstitutionTemplate | Type::TemplateHead => self.parse_template_literal(),
             _ => self.parse_identifier_expression(),
         }
     }
@@ -251,7 +413,7 @@ impl<'a> Parser<'a> {
         self.expect(Type::LParen)?;
         let mut expressions = vec![];
         while !self.at(Type::RParen) {
-            let expression = self.parse_assignment_expression_or_higher()?;
+            let expression = self.with_in(true, Self::parse_assignment_expression_or_higher)?;
             expressions.push(expression);
             if self.at(Type::Comma) {
                 self.bump_any();
@@ -261,8 +423,13 @@ impl<'a> Parser<'a> {
 
         let paren_span = self.end_span(span);
 
+        // the parenthesized expression covers the parameters of an arrow function
+        if self.at_arrow() {
+            return self.parse_arrow_function_expression(span, paren_span, expressions);
+        }
+
         if expressions.is_empty() {
-            return Err("Parenthesized expression must contain at least one expression".into());
+            return Err(ParseError::EmptyParenthesizedExpression { span: paren_span });
         }
 
         // ParenthesizedExpression is from acorn --preserveParens
@@ -297,7 +464,7 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
-            let element = self.parse_assignment_expression_or_higher()?;
+            let element = self.with_in(true, Self::parse_assignment_expression_or_higher)?;
             elements.push(ArrayExpressionElement::Expression(element));
 
             if self.at(Type::Comma) {
@@ -324,6 +491,46 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Section 13.2.8 Template Literals
+    pub(crate) fn parse_template_literal(&mut self) -> Result<Expression<'a>> {
+        let span = self.start_span();
+        let mut quasis = vec![];
+        let mut expressions = vec![];
+        if self.at(Type::NoSubstitutionTemplate) {
+            quasis.push(self.parse_template_element(true));
+        } else {
+            self.expect_peek_only(Type::TemplateHead)?;
+            quasis.push(self.parse_template_element(false));
+            loop {
+                expressions.push(self.with_in(true, Self::parse_expr)?);
+                match self.cur_kind() {
+                    Type::TemplateMiddle => quasis.push(self.parse_template_element(false)),
+                    Type::TemplateTail => {
+                        quasis.push(self.parse_template_element(true));
+                        break;
+                    }
+                    _ => return Err(self.unexpected()),
+                }
+            }
+        }
+        Ok(Expression::TemplateLiteral(Box::new(TemplateLiteral {
+            span: self.end_span(span),
+            quasis,
+            expressions,
+        })))
+    }
+
+    /// Parse the current template token, stripping its delimiters (`` ` ``, `}` and `${`)
+    fn parse_template_element(&mut self, tail: bool) -> TemplateElement<'a> {
+        let text = self.cur_string();
+        let closing = if tail { "`".len() } else { "${".len() };
+        let raw = &text[1..text.len() - closing];
+        let start = self.cur_token().start + 1;
+        let span = Span::new(start, start + raw.len());
+        self.bump_any();
+        TemplateElement { span, raw, tail }
+    }
+
     pub(crate) fn parse_literal_expression(&mut self) -> Result<Expression<'a>> {
         match self.cur_kind() {
             Type::Str => self
@@ -339,6 +546,9 @@ impl<'a> Parser<'a> {
             kind if kind.is_number() => self
                 .parse_literal_number()
                 .map(|literal| Expression::NumericLiteral(Box::new(literal))),
+            Type::RegExp => self
+                .parse_literal_regexp()
+                .map(|literal| Expression::RegExpLiteral(Box::new(literal))),
             _ => Err(self.unexpected()),
         }
     }
@@ -353,6 +563,23 @@ impl<'a> Parser<'a> {
         Ok(StringLiteral { span, value })
     }
 
+    pub(crate) fn parse_literal_regexp(&mut self) -> Result<RegExpLiteral<'a>> {
+        if !self.at(Type::RegExp) {
+            return Err(self.unexpected());
+        }
+        let raw = self.cur_string();
+        // the lexer guarantees a closing slash
+        let slash = raw.rfind('/').unwrap();
+        let (pattern, flags) = (&raw[1..slash], &raw[slash + 1..]);
+        let span = self.start_span();
+        self.bump_any();
+        Ok(RegExpLiteral {
+            span: self.end_span(span),
+            pattern,
+            flags,
+        })
+    }
+
     pub(crate) fn parse_literal_boolean(&mut self) -> Result<BooleanLiteral> {
         let span = self.start_span();
         let value = match self.cur_kind() {
@@ -376,13 +603,10 @@ impl<'a> Parser<'a> {
             return Err(self.unexpected());
         }
 
-        // TODO: implement our own number parser
-        use std::num::ParseFloatError;
-
         let raw = self.cur_string();
-        let value = raw
-            .parse::<f64>()
-            .map_err(|err: ParseFloatError| format!("Failed to parse number: {:?}", err))?;
+        let value = parse_number(raw, self.cur_kind()).ok_or(ParseError::InvalidNumber {
+            span: self.cur_token_span(),
+        })?;
         let span = self.start_span();
         self.bump_any();
         Ok(NumericLiteral { span, value, raw })
@@ -437,13 +661,17 @@ impl<'a> Parser<'a> {
                 break;
             }
 
-            // TODO:
             // Omit the In keyword for the grammar in 13.10 Relational Operators
             // RelationalExpression[In, Yield, Await] :
             // [+In] RelationalExpression[+In, ?Yield, ?Await] in ShiftExpression[?Yield, ?Await]
-            // if kind == Type::In && !self.ctx.has_in() {
-            //     break;
-            // }
+            if kind == Type::In && !self.in_allowed {
+                break;
+            }
+
+            // `-a ** b` is ambiguous, the operand of `**` cannot be a unary expression
+            if kind == Type::Star2 && matches!(lhs, Expression::UnaryExpression(_)) {
+                return Err(ParseError::UnaryBeforeExponentiation { span: lhs.span() });
+            }
 
             self.bump_any(); // bump operator
             let rhs = self.parse_binary_expression_or_higher(left_precedence)?;
@@ -498,3 +726,19 @@ impl<'a> Parser<'a> {
         (self.end_span(span), name)
     }
 }
+
+/// Whether a member access or a call in the chain ending with `expr` is optional
+fn is_optional_chain(expr: &Expression) -> bool {
+    let mut expr = expr;
+    loop {
+        expr = match expr {
+            Expression::StaticMemberExpression(member) if member.optional => return true,
+            Expression::ComputedMemberExpression(member) if member.optional => return true,
+            Expression::CallExpression(call) if call.optional => return true,
+            Expression::StaticMemberExpression(member) => &member.object,
+            Expression::ComputedMemberExpression(member) => &member.object,
+            Expression::CallExpression(call) => &call.callee,
+            _ => return false,
+        }
+    }
+}
@@ -8,7 +8,14 @@ impl<'a> Parser<'a> {
         &mut self,
         stmt_ctx: StatementContext,
     ) -> Result<Statement<'a>> {
-        let decl = self.parse_function_impl()?;
+        let span = self.start_span();
+        self.expect(Type::Function)?;
+        // only a function expression may be anonymous
+        if !self.cur_kind().is_identifier() {
+            return Err(self.unexpected());
+        }
+        let id = self.parse_function_id()?;
+        let decl = self.parse_function(span, id)?;
 
         Ok(Statement::FunctionDeclaration(decl))
     }
@@ -20,6 +27,11 @@ impl<'a> Parser<'a> {
         self.parse_function(span, id)
     }
 
+    pub(crate) fn parse_function_expression(&mut self) -> Result<Expression<'a>> {
+        let function = self.parse_function_impl()?;
+        Ok(Expression::FunctionExpression(function))
+    }
+
     pub(crate) fn parse_function_id(&mut self) -> Result<Option<Identifier<'a>>> {
         let id = self.cur_kind().is_identifier().then(|| {
             let (span, name) = self.parse_identifier_kind();
@@ -35,11 +47,11 @@ impl<'a> Parser<'a> {
 
         let mut params = vec![];
         while !self.at(Type::RParen) {
-            let (span, name) = self.parse_identifier_kind();
-            params.push(Identifier { span, name });
-            if self.at(Type::Comma) {
-                self.bump_any();
+            params.push(self.parse_identifier()?);
+            if !self.at(Type::Comma) {
+                break;
             }
+            self.bump_any();
         }
         self.expect(Type::RParen)?;
         Ok(Box::new(FormalParameters { span, params }))
@@ -66,15 +78,69 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    /// Whether the current token is the `=>` of an arrow function, which may not start a line
+    pub(crate) fn at_arrow(&self) -> bool {
+        self.at(Type::Arrow) && !self.cur_token().is_on_new_line
+    }
+
+    /// Parse an arrow function from `=>`, the parameters are parsed as expressions before it
+    pub(crate) fn parse_arrow_function_expression(
+        &mut self,
+        span: Span,
+        params_span: Span,
+        params: Vec<Expression<'a>>,
+    ) -> Result<Expression<'a>> {
+        let params = params
+            .into_iter()
+            .map(|param| match param {
+                Expression::Identifier(ident) => Ok(*ident),
+                param => Err(ParseError::InvalidParameter { span: param.span() }),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let params = Box::new(FormalParameters {
+            span: params_span,
+            params,
+        });
+        self.expect(Type::Arrow)?;
+
+        let expression = !self.at(Type::LCurly);
+        let body = if expression {
+            let body_span = self.start_span();
+            let expr = self.parse_assignment_expression_or_higher()?;
+            let span = self.end_span(body_span);
+            Box::new(FunctionBody {
+                span,
+                statements: vec![Statement::ExpressionStatement(Box::new(
+                    ExpressionStatement {
+                        span,
+                        expression: expr,
+                    },
+                ))],
+            })
+        } else {
+            self.parse_function_body()?
+        };
+
+        Ok(Expression::ArrowFunctionExpression(Box::new(
+            ArrowFunctionExpression {
+                span: self.end_span(span),
+                expression,
+                params,
+                body,
+            },
+        )))
+    }
+
     pub(crate) fn parse_function_body(&mut self) -> Result<Box<FunctionBody<'a>>> {
         let span = self.start_span();
         self.expect(Type::LCurly)?;
 
-        // TODO: consider context
-        // let (directives, statements) = self.context(Context::Return, Context::empty(), |p| {
-        //     p.parse_directives_and_statements(/* is_top_level */ false)
-        // })?;
-        let statements = self.parse_statements(false)?;
+        // TODO: consider the `Return` context
+        // labels do not cross function boundaries
+        let labels = std::mem::take(&mut self.labels);
+        let statements = self.with_in(true, |p| p.parse_statements(false));
+        self.labels = labels;
+        let statements = statements?;
 
         self.expect(Type::RCurly)?;
         Ok(Box::new(FunctionBody {
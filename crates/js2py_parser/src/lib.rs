@@ -1,16 +1,23 @@
 pub mod ast;
+pub mod binding;
+pub mod class;
+pub mod error;
 pub mod expr;
 pub mod func;
+pub mod line_index;
 pub mod obj;
 pub mod op;
 pub mod parser;
 pub mod stmt;
 pub mod syntax;
+pub mod visit;
 
 pub use js2py_lexer::token::*;
 pub use parser::*;
 
-pub type Result<T> = std::result::Result<T, String>;
+pub use error::ParseError;
+
+pub type Result<T> = std::result::Result<T, ParseError>;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum StatementContext {
@@ -19,5 +26,6 @@ pub enum StatementContext {
     While,
     With,
     For,
+    Label,
     StatementList,
 }
@@ -0,0 +1,114 @@
+//! Conversion of byte offsets to line and column numbers.
+
+use serde::Serialize;
+use std::fmt;
+
+/// A position in the source text, lines and columns start at 1
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub line: usize,
+    /// Column counted in UTF-8 bytes
+    pub column: usize,
+    /// Column counted in UTF-16 code units, as used by editors and the language server protocol
+    pub utf16_column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A character that is longer in UTF-8 than in UTF-16
+#[derive(Debug, Clone, Copy)]
+struct WideChar {
+    /// Offset from the start of the line
+    start: usize,
+    len_utf8: usize,
+    len_utf16: usize,
+}
+
+/// Index of the line starts of a source text, built once and queried for every location
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    /// Offset of the first byte of every line
+    line_starts: Vec<usize>,
+    /// Non-ASCII characters of every line, in order
+    wide_chars: Vec<Vec<WideChar>>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars = vec![vec![]];
+        let mut chars = source.char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            // LineTerminatorSequence :: <LF> | <CR> [lookahead ≠ <LF>] | <LS> | <PS> | <CR><LF>
+            let is_line_terminator = match c {
+                '\n' | '\u{2028}' | '\u{2029}' => true,
+                '\r' => chars.peek().map(|&(_, c)| c) != Some('\n'),
+                _ => false,
+            };
+            if is_line_terminator {
+                line_starts.push(offset + c.len_utf8());
+                wide_chars.push(vec![]);
+            } else if !c.is_ascii() {
+                wide_chars.last_mut().unwrap().push(WideChar {
+                    start: offset - line_starts.last().unwrap(),
+                    len_utf8: c.len_utf8(),
+                    len_utf16: c.len_utf16(),
+                });
+            }
+        }
+        Self {
+            line_starts,
+            wide_chars,
+        }
+    }
+
+    /// Number of lines, a trailing line terminator starts an empty line
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Byte offset of the start of `line` (starting at 1)
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line.checked_sub(1)?).copied()
+    }
+
+    /// The location of a byte offset
+    pub fn location(&self, offset: usize) -> Location {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = offset - self.line_starts[line];
+        let utf16_column = self.wide_chars[line]
+            .iter()
+            .take_while(|c| c.start < column)
+            .fold(column, |col, c| col - c.len_utf8 + c.len_utf16);
+        Location {
+            line: line + 1,
+            column: column + 1,
+            utf16_column: utf16_column + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn line_index_test() {
+        let index = LineIndex::new("a\nbé😀c\r\nd\re");
+        assert_eq!(index.line_count(), 4);
+        let loc = |offset| {
+            let l = index.location(offset);
+            (l.line, l.column, l.utf16_column)
+        };
+        assert_eq!(loc(0), (1, 1, 1));
+        assert_eq!(loc(2), (2, 1, 1));
+        // after `bé😀`
+        assert_eq!(loc(9), (2, 8, 5));
+        assert_eq!(loc(12), (3, 1, 1));
+        assert_eq!(loc(14), (4, 1, 1));
+    }
+}
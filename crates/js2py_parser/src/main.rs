@@ -14,17 +14,17 @@ fn main() {
 
     let mut parser = Parser::new(&source);
 
-    let program = parser.parse();
+    let (program, errors) = parser.parse_with_recovery();
 
-    match program {
-        Ok(program) => {
-            // Serialize the program to a JSON string and write it to file:
-            let path = format!("{}.ast.json", filename);
-            let writer = std::fs::File::create(path).unwrap();
-            serde_json::to_writer_pretty(writer, &program).unwrap();
-        }
-        Err(e) => {
-            eprintln!("{}", e);
-        }
+    // Serialize the program to a JSON string and write it to file, even if it is partial:
+    let path = format!("{}.ast.json", filename);
+    let writer = std::fs::File::create(path).unwrap();
+    serde_json::to_writer_pretty(writer, &program).unwrap();
+
+    for e in &errors {
+        eprintln!("{}\n", e.render(&source, parser.line_index(), filename));
+    }
+    if !errors.is_empty() {
+        std::process::exit(1);
     }
 }
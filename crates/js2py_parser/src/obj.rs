@@ -1,5 +1,6 @@
 use super::*;
 use crate::ast::*;
+use syntax::lex::TokenTypeUtil;
 
 impl<'a> Parser<'a> {
     pub(crate) fn parse_object_expression(&mut self) -> Result<Expression<'a>> {
@@ -25,7 +26,7 @@ impl<'a> Parser<'a> {
         let span = self.start_span();
         let key = self.parse_property_key()?;
         self.expect(Type::Colon)?;
-        let value = self.parse_assignment_expression_or_higher()?;
+        let value = self.with_in(true, Self::parse_assignment_expression_or_higher)?;
         Ok(ObjectProperty {
             span: self.end_span(span),
             key,
@@ -34,7 +35,8 @@ impl<'a> Parser<'a> {
     }
     pub(crate) fn parse_property_key(&mut self) -> Result<PropertyKey<'a>> {
         let key = match self.cur_kind() {
-            Type::Identifier => {
+            // keywords are valid property names
+            kind if kind.is_identifier_name() => {
                 let (span, name) = self.parse_identifier_kind();
                 PropertyKey::IdentifierName(IdentifierName { span, name })
             }
@@ -42,7 +44,7 @@ impl<'a> Parser<'a> {
                 let lit = self.parse_literal_string()?;
                 PropertyKey::StringLiteral(lit)
             }
-            Type::Decimal => {
+            kind if kind.is_number() => {
                 let lit = self.parse_literal_number()?;
                 PropertyKey::NumericLiteral(lit)
             }
@@ -1,37 +1,97 @@
 use super::*;
 use crate::ast::*;
-use std::sync::mpsc;
 
-use js2py_lexer::lexer::token_stream;
+use js2py_lexer::error::LexError;
+use js2py_lexer::lexer::Lexer;
+use line_index::LineIndex;
+use std::cell::OnceCell;
 use syntax::lex::TokenTypeUtil;
 
 pub struct Parser<'a> {
     /// Source Code
     source: &'a str,
 
-    lexer: mpsc::Receiver<Token>,
+    lexer: Lexer<'a>,
 
     /// Current Token consumed from the lexer
     cur_token: Token,
 
     /// The end range of the previous token
     prev_token_end: usize,
+
+    /// Line starts of the source, for error locations, built on first use
+    line_index: OnceCell<LineIndex>,
+
+    /// Whether to recover from errors at statement boundaries instead of stopping
+    pub(crate) recovering: bool,
+
+    /// Errors recovered from so far
+    pub(crate) errors: Vec<ParseError>,
+
+    /// Whether `in` is a relational operator, it is not in the head of a `for` loop
+    pub(crate) in_allowed: bool,
+
+    /// The labels of the enclosing statements in the current function, and whether each one
+    /// labels a loop
+    pub(crate) labels: Vec<(&'a str, bool)>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(source: &'a str) -> Self {
         Self {
             source,
-            lexer: token_stream(source),
+            lexer: Lexer::new(source),
             cur_token: Token::default(),
             prev_token_end: 0,
+            line_index: OnceCell::new(),
+            recovering: false,
+            errors: vec![],
+            in_allowed: true,
+            labels: vec![],
         }
     }
 
-    pub fn parse(&mut self) -> Result<Program> {
+    pub fn line_index(&self) -> &LineIndex {
+        self.line_index.get_or_init(|| LineIndex::new(self.source))
+    }
+
+    pub fn parse(&mut self) -> Result<Program<'a>> {
         self.parse_program()
     }
 
+    /// Parse the whole source even if it has errors
+    ///
+    /// Statements that fail to parse are replaced by `ErrorStatement`s, and parenthesized
+    /// conditions that fail to parse by `ErrorExpression`s. The errors are returned in source
+    /// order, including the ones reported by the lexer in the skipped regions.
+    pub fn parse_with_recovery(&mut self) -> (Program<'a>, Vec<ParseError>) {
+        self.recovering = true;
+        let program = match self.parse_program() {
+            Ok(program) => program,
+            // statement lists recover from every error
+            Err(_) => unreachable!(),
+        };
+        let mut errors = std::mem::take(&mut self.errors);
+        for error in self.lexer.errors() {
+            let reported = errors
+                .iter()
+                .any(|e| matches!(e, ParseError::LexError(e) if e.start == error.start));
+            if !reported {
+                errors.push(ParseError::LexError(error.clone()));
+            }
+        }
+        errors.sort_by_key(|e| e.span().start);
+        (program, errors)
+    }
+
+    /// Run `f` with `in` allowed or not as a relational operator, the `[In]` grammar parameter
+    pub(crate) fn with_in<T>(&mut self, allowed: bool, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.in_allowed, allowed);
+        let result = f(self);
+        self.in_allowed = outer;
+        result
+    }
+
     pub fn parse_expression(mut self) -> Result<Expression<'a>> {
         // initialize cur_token and prev_token by moving onto the first token
         self.bump_any();
@@ -109,7 +169,7 @@ impl<'a> Parser<'a> {
     /// Move to the next token
     pub(crate) fn advance(&mut self) {
         let mut token = Token::default();
-        while let Ok(t) = self.lexer.recv() {
+        for t in self.lexer.by_ref() {
             if matches!(t.typ, Type::LineTerminator) {
                 continue;
             } else {
@@ -123,10 +183,14 @@ impl<'a> Parser<'a> {
 
     pub(crate) fn expect_peek_only(&mut self, kind: Type) -> Result<()> {
         if !self.at(kind) {
-            return Err(format!(
-                "Expected token type {:?} but got {:?}",
-                kind, self.cur_token
-            ));
+            if let Some(error) = self.lex_error() {
+                return Err(ParseError::LexError(error.clone()));
+            }
+            return Err(ParseError::UnexpectedToken {
+                expected: Some(kind),
+                found: self.cur_kind(),
+                span: self.cur_token_span(),
+            });
         }
         Ok(())
     }
@@ -137,8 +201,73 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    pub(crate) fn unexpected(&self) -> String {
-        format!("Unexpected token: {:?}", self.cur_token)
+    pub(crate) fn unexpected(&self) -> ParseError {
+        if let Some(error) = self.lex_error() {
+            return ParseError::LexError(error.clone());
+        }
+        ParseError::UnexpectedToken {
+            expected: None,
+            found: self.cur_kind(),
+            span: self.cur_token_span(),
+        }
+    }
+
+    pub(crate) fn cur_token_span(&self) -> Span {
+        Span::new(self.cur_token.start, self.cur_token.end)
+    }
+
+    /// The error reported by the lexer if we are at a `LexerError` token
+    pub(crate) fn lex_error(&self) -> Option<&LexError> {
+        if !self.at(Type::LexerError) {
+            return None;
+        }
+        self.lexer.errors().last()
+    }
+
+    /// Skip tokens up to the next statement boundary after an error in the statement starting
+    /// at `span`: after a `;`, or before a `}` or a keyword starting a statement
+    pub(crate) fn synchronize(&mut self, span: Span) {
+        // always make progress, the error may be at the first token of the statement
+        if self.cur_token.start == span.start && !self.at(Type::EOF) {
+            self.bump_any();
+        }
+        let mut depth = 0;
+        loop {
+            match self.cur_kind() {
+                Type::EOF => break,
+                Type::Semicolon if depth == 0 => {
+                    self.bump_any();
+                    break;
+                }
+                Type::RCurly if depth == 0 => break,
+                Type::RCurly => depth -= 1,
+                Type::LCurly => depth += 1,
+                kind if depth == 0 && kind.is_statement_start() => break,
+                _ => {}
+            }
+            self.bump_any();
+        }
+    }
+
+    /// Skip tokens up to the `)` closing the current parenthesized expression, returns false if
+    /// a statement boundary is reached first
+    pub(crate) fn skip_parenthesized(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            match self.cur_kind() {
+                Type::RParen if depth == 0 => return true,
+                Type::RParen => depth -= 1,
+                Type::LParen => depth += 1,
+                Type::Semicolon | Type::LCurly | Type::RCurly | Type::EOF => return false,
+                _ => {}
+            }
+            self.bump_any();
+        }
+    }
+
+    /// The span of the tokens skipped since `span` started, which may be empty
+    pub(crate) fn recovered_span(&self, span: Span) -> Span {
+        Span::new(span.start, self.prev_token_end.max(span.start))
     }
 
     pub(crate) fn can_insert_semicolon(&self) -> bool {
@@ -146,18 +275,14 @@ impl<'a> Parser<'a> {
         if kind == Type::Semicolon {
             return true;
         }
-        // TODO: check if cur_token is on a new line
-        kind == Type::RCurly || kind.is_eof()
-        // || self.cur_token().is_on_new_line
+        kind == Type::RCurly || kind.is_eof() || self.cur_token().is_on_new_line
     }
 
     pub(crate) fn auto_semicoclon_insertion(&mut self) -> Result<()> {
         if !self.can_insert_semicolon() {
-            let span = Span::new(self.prev_token_end, self.prev_token_end);
-            return Err(format!(
-                "Expected a semicolon or an implicit semicolon after a statement, but found none at {:?}",
-                span
-            ));
+            return Err(ParseError::MissingSemicolon {
+                span: Span::new(self.prev_token_end, self.prev_token_end),
+            });
         }
         if self.at(Type::Semicolon) {
             self.advance();
@@ -171,6 +296,7 @@ mod test {
     use core::panic;
 
     use super::*;
+    use crate::syntax::operator::{AssignmentOperator, BinaryOperator, LogicalOperator};
     #[test]
     fn parse_empty_smoke_test() {
         let source = "";
@@ -304,6 +430,20 @@ mod test {
                 panic!()
             }
         }
+
+        // a declaration needs a name
+        let err = Parser::new("function (a) {}").parse().unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnexpectedToken {
+                found: Type::LParen,
+                ..
+            }
+        ));
+        let (program, errors) = Parser::new("function (a) {}\nif (a) f()").parse_with_recovery();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(program.body[0], Statement::ErrorStatement(_)));
+        assert!(matches!(program.body[1], Statement::IfStatement(_)));
     }
 
     #[test]
@@ -391,6 +531,726 @@ mod test {
         }
     }
 
+    #[test]
+    fn parse_template_literal_test() {
+        let source = "`a${b}c${ {d: 1}.d }e`";
+        let parser = Parser::new(source);
+        let ret = parser.parse_expression();
+        match ret {
+            Ok(Expression::TemplateLiteral(t)) => {
+                println!("{:#?}", t);
+                let raws: Vec<_> = t.quasis.iter().map(|q| q.raw).collect();
+                assert_eq!(raws, ["a", "c", "e"]);
+                assert_eq!(t.expressions.len(), 2);
+            }
+            r => {
+                println!("{:?}", r);
+                panic!()
+            }
+        }
+    }
+
+    #[test]
+    fn parse_regexp_literal_test() {
+        let source = "s.replace(/[/]+/gi, a / b)";
+        let parser = Parser::new(source);
+        let ret = parser.parse_expression();
+        match ret {
+            Ok(Expression::CallExpression(call)) => {
+                println!("{:#?}", call);
+                match &call.arguments[0] {
+                    Expression::RegExpLiteral(re) => {
+                        assert_eq!((re.pattern, re.flags), ("[/]+", "gi"));
+                    }
+                    _ => panic!(),
+                }
+                assert!(matches!(call.arguments[1], Expression::BinaryExpression(_)));
+            }
+            r => {
+                println!("{:?}", r);
+                panic!()
+            }
+        }
+    }
+
+    #[test]
+    fn parse_numeric_literal_test() {
+        let cases = [
+            ("0xFF", 255.0),
+            ("0b1010", 10.0),
+            ("0o777", 511.0),
+            ("1_000_000", 1e6),
+            (".5", 0.5),
+            ("1.5e3", 1500.0),
+            ("10n", 10.0),
+            ("0x1_0n", 16.0),
+        ];
+        for (source, value) in cases {
+            let parser = Parser::new(source);
+            match parser.parse_expression() {
+                Ok(Expression::NumericLiteral(num)) => {
+                    assert_eq!(num.value, value);
+                    assert_eq!(num.raw, source);
+                }
+                r => {
+                    println!("{:?}", r);
+                    panic!()
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_arrow_function_test() {
+        let cases = [
+            ("x => x * 2", 1, true),
+            ("(a, b) => { return a + b }", 2, false),
+            ("() => ({})", 0, true),
+            ("(a,) => a", 1, true),
+        ];
+        for (source, params, expression) in cases {
+            let parser = Parser::new(source);
+            match parser.parse_expression() {
+                Ok(Expression::ArrowFunctionExpression(arrow)) => {
+                    assert_eq!(arrow.params.params.len(), params, "{}", source);
+                    assert_eq!(arrow.expression, expression, "{}", source);
+                }
+                r => {
+                    println!("{:?}", r);
+                    panic!("{}", source)
+                }
+            }
+        }
+
+        let parser = Parser::new("items.map(x => x + 1, ctx)");
+        match parser.parse_expression() {
+            Ok(Expression::CallExpression(call)) => {
+                assert_eq!(call.arguments.len(), 2);
+                assert!(matches!(
+                    call.arguments[0],
+                    Expression::ArrowFunctionExpression(_)
+                ));
+            }
+            r => {
+                println!("{:?}", r);
+                panic!()
+            }
+        }
+
+        let err = Parser::new("(a, 1) => a").parse_expression().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidParameter { .. }));
+        assert!(Parser::new("a\n=> a").parse().is_err());
+    }
+
+    #[test]
+    fn parse_function_expression_test() {
+        let parser = Parser::new("(function () { return 1 })()");
+        match parser.parse_expression() {
+            Ok(Expression::CallExpression(call)) => match &call.callee {
+                Expression::ParenthesizedExpression(paren) => match &paren.expression {
+                    Expression::FunctionExpression(f) => assert!(f.id.is_none()),
+                    r => panic!("{:?}", r),
+                },
+                r => panic!("{:?}", r),
+            },
+            r => panic!("{:?}", r),
+        }
+
+        let parser = Parser::new("f(function g(a, b) {})");
+        match parser.parse_expression() {
+            Ok(Expression::CallExpression(call)) => match &call.arguments[0] {
+                Expression::FunctionExpression(f) => {
+                    assert_eq!(f.id.as_ref().unwrap().name, "g");
+                    assert_eq!(f.params.params.len(), 2);
+                }
+                r => panic!("{:?}", r),
+            },
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn parse_class_test() {
+        let source = "class A extends B {
+  static count = 0
+  x
+  constructor(a) { super(a); this.a = a }
+  get size() { return this.a }
+  set size(v) {}
+  static create() {}
+  static() {}
+  get = 1;
+}
+c = class {}";
+        let mut parser = Parser::new(source);
+        let program = parser.parse().unwrap();
+        let Statement::ClassDeclaration(class) = &program.body[0] else {
+            panic!()
+        };
+        assert_eq!(class.id.as_ref().unwrap().name, "A");
+        assert!(matches!(class.super_class, Some(Expression::Identifier(_))));
+        let members: Vec<_> = class
+            .body
+            .iter()
+            .map(|element| match element {
+                ClassElement::MethodDefinition(m) => {
+                    let PropertyKey::IdentifierName(key) = &m.key else {
+                        panic!()
+                    };
+                    (key.name, Some(m.kind), m.is_static)
+                }
+                ClassElement::PropertyDefinition(p) => {
+                    let PropertyKey::IdentifierName(key) = &p.key else {
+                        panic!()
+                    };
+                    (key.name, None, p.is_static)
+                }
+            })
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("count", None, true),
+                ("x", None, false),
+                (
+                    "constructor",
+                    Some(MethodDefinitionKind::Constructor),
+                    false
+                ),
+                ("size", Some(MethodDefinitionKind::Get), false),
+                ("size", Some(MethodDefinitionKind::Set), false),
+                ("create", Some(MethodDefinitionKind::Method), true),
+                ("static", Some(MethodDefinitionKind::Method), false),
+                ("get", None, false),
+            ]
+        );
+        assert!(matches!(
+            &program.body[1],
+            Statement::ExpressionStatement(e) if matches!(
+                &e.expression,
+                Expression::AssignmentExpression(a) if matches!(a.right, Expression::ClassExpression(_))
+            )
+        ));
+    }
+
+    #[test]
+    fn parse_try_statement_test() {
+        let source =
+            "try { f() } catch (e) { g(e) } finally { h() }\ntry {} catch {}\ntry {} finally {}";
+        let mut parser = Parser::new(source);
+        let program = parser.parse().unwrap();
+        let shapes: Vec<_> = program
+            .body
+            .iter()
+            .map(|stmt| {
+                let Statement::TryStatement(t) = stmt else {
+                    panic!()
+                };
+                (
+                    t.handler.as_ref().map(|h| h.param.as_ref().map(|p| p.name)),
+                    t.finalizer.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            shapes,
+            [(Some(Some("e")), true), (Some(None), false), (None, true)]
+        );
+
+        let err = Parser::new("try {}\nx").parse().unwrap_err();
+        assert!(matches!(err, ParseError::MissingCatchOrFinally { .. }));
+        assert!(Parser::new("try {} catch (1) {}").parse().is_err());
+    }
+
+    #[test]
+    fn parse_for_in_of_test() {
+        let program = Parser::new("for (const k in a in b) {}").parse().unwrap();
+        let Statement::ForInStatement(for_in) = &program.body[0] else {
+            panic!()
+        };
+        assert!(matches!(
+            for_in.left,
+            ForStatementLeft::VariableDeclaration(_)
+        ));
+        assert!(matches!(for_in.right, Expression::BinaryExpression(_)));
+
+        let program = Parser::new("for (x.y of [1, 2]) ;").parse().unwrap();
+        let Statement::ForOfStatement(for_of) = &program.body[0] else {
+            panic!()
+        };
+        assert!(matches!(
+            for_of.left,
+            ForStatementLeft::Expression(Expression::StaticMemberExpression(_))
+        ));
+
+        // `in` is a relational operator again in parentheses
+        let program = Parser::new("for (let x = (a in b); x;) {}")
+            .parse()
+            .unwrap();
+        assert!(matches!(program.body[0], Statement::ForStatement(_)));
+
+        let err = Parser::new("for (f() in o) {}").parse().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidAssignmentTarget { .. }));
+        assert!(Parser::new("for (let x = 1 of xs) {}").parse().is_err());
+        assert!(Parser::new("for (x of a, b) {}").parse().is_err());
+    }
+
+    #[test]
+    fn parse_labeled_statement_test() {
+        let source = "outer: for (;;) { inner: while (a) { if (b) continue outer; break inner } }\nblock: { break block }";
+        let program = Parser::new(source).parse().unwrap();
+        let Statement::LabeledStatement(outer) = &program.body[0] else {
+            panic!()
+        };
+        assert_eq!(outer.label.name, "outer");
+        assert!(matches!(outer.body, Statement::ForStatement(_)));
+        assert!(matches!(program.body[1], Statement::LabeledStatement(_)));
+
+        // a label on the next line is a new statement
+        let program = Parser::new("a: while (x) { break\na }").parse().unwrap();
+        let Statement::LabeledStatement(a) = &program.body[0] else {
+            panic!()
+        };
+        let Statement::WhileStatement(w) = &a.body else {
+            panic!()
+        };
+        let Statement::BlockStatement(block) = &w.body else {
+            panic!()
+        };
+        assert!(matches!(&block.body[0], Statement::BreakStatement(b) if b.label.is_none()));
+
+        let err = Parser::new("while (x) { break nowhere }")
+            .parse()
+            .unwrap_err();
+        assert!(matches!(err, ParseError::UndefinedLabel { .. }));
+        assert_eq!(err.to_string(), "Undefined label `nowhere`");
+        let err = Parser::new("a: { continue a }").parse().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidContinueLabel { .. }));
+        // labels are not visible in nested functions
+        let err = Parser::new("a: while (x) { f(function () { break a }) }")
+            .parse()
+            .unwrap_err();
+        assert!(matches!(err, ParseError::UndefinedLabel { .. }));
+    }
+
+    #[test]
+    fn parse_conditional_expression_test() {
+        let program = Parser::new("x = a ? b : c ? d : e;").parse().unwrap();
+        let Statement::ExpressionStatement(stmt) = &program.body[0] else {
+            panic!()
+        };
+        let Expression::AssignmentExpression(assign) = &stmt.expression else {
+            panic!()
+        };
+        let Expression::ConditionalExpression(cond) = &assign.right else {
+            panic!()
+        };
+        assert!(matches!(cond.test, Expression::Identifier(_)));
+        assert!(matches!(
+            cond.alternate,
+            Expression::ConditionalExpression(_)
+        ));
+
+        // the branches are assignment expressions
+        let program = Parser::new("a ? b = 1 : c = 2;").parse().unwrap();
+        let Statement::ExpressionStatement(stmt) = &program.body[0] else {
+            panic!()
+        };
+        let Expression::ConditionalExpression(cond) = &stmt.expression else {
+            panic!()
+        };
+        assert!(matches!(
+            cond.consequent,
+            Expression::AssignmentExpression(_)
+        ));
+        assert!(matches!(
+            cond.alternate,
+            Expression::AssignmentExpression(_)
+        ));
+
+        // `in` is allowed in the consequent of a for-head initializer, but not in the alternate
+        let program = Parser::new("for (var x = a ? b in c : d; x;) {}")
+            .parse()
+            .unwrap();
+        assert!(matches!(program.body[0], Statement::ForStatement(_)));
+        let program = Parser::new("for (let x = a ? b : c in d) {}").parse();
+        assert!(program.is_err());
+    }
+
+    #[test]
+    fn parse_exponentiation_and_coalesce_test() {
+        let program = Parser::new("a ** b ** c; a ?? b ?? c; x ??= y;")
+            .parse()
+            .unwrap();
+        let expressions: Vec<_> = program
+            .body
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::ExpressionStatement(e) => &e.expression,
+                _ => panic!(),
+            })
+            .collect();
+        // `**` nests to the right, `??` to the left
+        let Expression::BinaryExpression(pow) = expressions[0] else {
+            panic!()
+        };
+        assert_eq!(pow.operator, BinaryOperator::Exponential);
+        assert!(matches!(pow.right, Expression::BinaryExpression(_)));
+        let Expression::LogicalExpression(coalesce) = expressions[1] else {
+            panic!()
+        };
+        assert_eq!(coalesce.operator, LogicalOperator::Coalesce);
+        assert!(matches!(coalesce.left, Expression::LogicalExpression(_)));
+        assert!(matches!(
+            expressions[2],
+            Expression::AssignmentExpression(a) if a.operator == AssignmentOperator::LogicalNullish
+        ));
+
+        let err = Parser::new("-a ** 2;").parse().unwrap_err();
+        assert!(matches!(err, ParseError::UnaryBeforeExponentiation { .. }));
+        assert!(Parser::new("(-a) ** 2; -(a ** 2); a ** -2;")
+            .parse()
+            .is_ok());
+    }
+
+    #[test]
+    fn parse_optional_chain_test() {
+        let program = Parser::new("a?.b.c(); f?.(x)?.[0]; x = a?.5:1;")
+            .parse()
+            .unwrap();
+        let Statement::ExpressionStatement(stmt) = &program.body[0] else {
+            panic!()
+        };
+        let Expression::ChainExpression(chain) = &stmt.expression else {
+            panic!()
+        };
+        let Expression::CallExpression(call) = &chain.expression else {
+            panic!()
+        };
+        assert!(!call.optional);
+        let Expression::StaticMemberExpression(c) = &call.callee else {
+            panic!()
+        };
+        assert!(matches!(&c.object, Expression::StaticMemberExpression(b) if b.optional));
+
+        let Statement::ExpressionStatement(stmt) = &program.body[1] else {
+            panic!()
+        };
+        let Expression::ChainExpression(chain) = &stmt.expression else {
+            panic!()
+        };
+        let Expression::ComputedMemberExpression(member) = &chain.expression else {
+            panic!()
+        };
+        assert!(member.optional);
+        assert!(matches!(&member.object, Expression::CallExpression(f) if f.optional));
+
+        // `?.` followed by a digit is a `?` and a number
+        let Statement::ExpressionStatement(stmt) = &program.body[2] else {
+            panic!()
+        };
+        assert!(matches!(
+            &stmt.expression,
+            Expression::AssignmentExpression(a) if matches!(a.right, Expression::ConditionalExpression(_))
+        ));
+
+        let err = Parser::new("a?.b = 1;").parse().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidAssignmentTarget { .. }));
+    }
+
+    #[test]
+    fn parse_new_expression_test() {
+        let program = Parser::new("new a.b(c).d; new F()(); new G; new.target;")
+            .parse()
+            .unwrap();
+        let expressions: Vec<_> = program
+            .body
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::ExpressionStatement(e) => &e.expression,
+                _ => panic!(),
+            })
+            .collect();
+        // the first arguments belong to `new`
+        let Expression::StaticMemberExpression(d) = expressions[0] else {
+            panic!()
+        };
+        let Expression::NewExpression(new) = &d.object else {
+            panic!()
+        };
+        assert!(matches!(new.callee, Expression::StaticMemberExpression(_)));
+        assert_eq!(new.arguments.len(), 1);
+        let Expression::CallExpression(call) = expressions[1] else {
+            panic!()
+        };
+        assert!(matches!(call.callee, Expression::NewExpression(_)));
+        assert!(matches!(expressions[2], Expression::NewExpression(g) if g.arguments.is_empty()));
+        assert!(matches!(
+            expressions[3],
+            Expression::MetaProperty(m) if m.meta.name == "new" && m.property.name == "target"
+        ));
+
+        let err = Parser::new("new a?.b();").parse().unwrap_err();
+        assert!(matches!(err, ParseError::OptionalChainInNew { .. }));
+        assert!(Parser::new("new.foo;").parse().is_err());
+    }
+
+    #[test]
+    fn parse_update_expression_test() {
+        let program = Parser::new("a[i++] = --b.c;").parse().unwrap();
+        let Statement::ExpressionStatement(stmt) = &program.body[0] else {
+            panic!()
+        };
+        let Expression::AssignmentExpression(assign) = &stmt.expression else {
+            panic!()
+        };
+        let AssignmentTarget::ComputedMemberExpression(member) = &assign.left else {
+            panic!()
+        };
+        assert!(matches!(&member.expression, Expression::UpdateExpression(u) if !u.prefix));
+        assert!(matches!(&assign.right, Expression::UpdateExpression(u) if u.prefix));
+
+        for source in ["f()++;", "++1;", "--a++;", "++-a;"] {
+            let err = Parser::new(source).parse().unwrap_err();
+            assert!(
+                matches!(err, ParseError::InvalidAssignmentTarget { .. }),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn parse_binding_pattern_test() {
+        let source = "let a, b = 1, [c, , ...d] = e, { f, g: [h = 2], ...i } = j;";
+        let program = Parser::new(source).parse().unwrap();
+        let Statement::VariableDeclarationStatement(var) = &program.body[0] else {
+            panic!()
+        };
+        assert_eq!(var.declarations.len(), 4);
+        assert!(var.declarations[0].init.is_none());
+        let BindingPattern::ArrayPattern(array) = &var.declarations[2].id else {
+            panic!()
+        };
+        assert_eq!(array.elements.len(), 2);
+        assert!(array.elements[1].is_none());
+        assert!(array.rest.is_some());
+        let BindingPattern::ObjectPattern(object) = &var.declarations[3].id else {
+            panic!()
+        };
+        assert!(object.properties[0].shorthand);
+        let BindingPattern::ArrayPattern(g) = &object.properties[1].value else {
+            panic!()
+        };
+        assert!(matches!(
+            g.elements[0],
+            Some(BindingPattern::AssignmentPattern(_))
+        ));
+        assert!(object.rest.is_some());
+
+        // patterns and constants must be initialized, except in a for-in or for-of head
+        for source in ["let [a];", "const a;", "for (let {a}; ;) {}"] {
+            let err = Parser::new(source).parse().unwrap_err();
+            assert!(
+                matches!(err, ParseError::MissingInitializer { .. }),
+                "{}",
+                source
+            );
+        }
+        assert!(Parser::new("for (const [k, v] of m) {}").parse().is_ok());
+        // the rest element is the last one
+        assert!(Parser::new("let [...a, b] = c;").parse().is_err());
+    }
+
+    #[test]
+    fn error_location_test() {
+        let source = "a = 1;\nb = (é, ;";
+        let mut parser = Parser::new(source);
+        let err = parser.parse().unwrap_err();
+        assert_eq!(err.to_string(), "Unexpected `;`");
+        let (start, _) = err.span().to_location(parser.line_index());
+        assert_eq!(start.to_string(), "2:10");
+        assert_eq!(
+            err.render(source, parser.line_index(), "test.js"),
+            "error: Unexpected `;`\n --> test.js:2:10\n  |\n2 | b = (é, ;\n  |         ^"
+        );
+    }
+
+    #[test]
+    fn lex_error_test() {
+        let source = "a = 'unterminated";
+        let mut parser = Parser::new(source);
+        let ret = parser.parse();
+        let err = ret.unwrap_err();
+        assert!(matches!(err, ParseError::LexError(_)));
+        assert_eq!(
+            err.render(source, parser.line_index(), "test.js"),
+            "error: Unexpected end of input\n --> test.js:1:5\n  |\n1 | a = 'unterminated\n  |     ^^^^^^^^^^^^^\n  |\n  = help: add the closing quote"
+        );
+    }
+
+    #[test]
+    fn error_recovery_test() {
+        let source = "a = 1;\nb c d;\nfunction f() {\n  x = ;\n  return x\n}\nif (+) g()\n} e = 'oops\nif (h) h()";
+        let mut parser = Parser::new(source);
+        let (program, errors) = parser.parse_with_recovery();
+        let messages: Vec<_> = errors
+            .iter()
+            .map(|e| {
+                let (start, _) = e.span().to_location(parser.line_index());
+                format!("{}: {}", start, e)
+            })
+            .collect();
+        assert_eq!(messages.len(), 5, "{:#?}", messages);
+        assert!(messages[0].starts_with("2:2: "));
+        assert!(messages[1].starts_with("4:7: "));
+        assert!(messages[2].starts_with("7:6: "));
+        assert!(messages[3].starts_with("8:1: "));
+        assert!(messages[4].starts_with("8:7: "));
+
+        let kinds: Vec<_> = program
+            .body
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::ExpressionStatement(_) => "expression",
+                Statement::ErrorStatement(_) => "error",
+                Statement::FunctionDeclaration(_) => "function",
+                Statement::IfStatement(_) => "if",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            ["expression", "error", "function", "if", "error", "if"]
+        );
+        let Statement::FunctionDeclaration(f) = &program.body[2] else {
+            panic!()
+        };
+        let body = &f.body.as_ref().unwrap().statements;
+        assert!(matches!(body[0], Statement::ErrorStatement(_)));
+        assert!(matches!(body[1], Statement::ReturnStatement(_)));
+        let Statement::IfStatement(i) = &program.body[3] else {
+            panic!()
+        };
+        assert!(matches!(i.test, Expression::ErrorExpression(_)));
+    }
+
+    #[test]
+    fn error_recovery_parameters_test() {
+        // an unclosed parameter list ends at the end of the input
+        for source in ["function f(", "x = function (", "f(function (a, "] {
+            let (_, errors) = Parser::new(source).parse_with_recovery();
+            assert!(
+                matches!(
+                    errors[..],
+                    [ParseError::UnexpectedToken {
+                        found: Type::EOF,
+                        ..
+                    }]
+                ),
+                "{}: {:?}",
+                source,
+                errors
+            );
+        }
+        // a parameter that is not an identifier
+        let (program, errors) =
+            Parser::new("function f(a = 1) {}\nif (g) g()").parse_with_recovery();
+        assert!(
+            matches!(
+                errors[..],
+                [ParseError::UnexpectedToken {
+                    found: Type::Eq,
+                    ..
+                }]
+            ),
+            "{:?}",
+            errors
+        );
+        assert!(matches!(program.body[0], Statement::ErrorStatement(_)));
+        assert!(matches!(
+            program.body.last(),
+            Some(Statement::IfStatement(_))
+        ));
+        assert!(Parser::new("function f(a b) {}").parse().is_err());
+    }
+
+    /// The AST of a program without spans, to compare programs with different layouts
+    fn ast_shape(source: &str) -> serde_json::Value {
+        fn strip(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    map.remove("span");
+                    map.remove("source_text");
+                    map.values_mut().for_each(strip);
+                }
+                serde_json::Value::Array(values) => values.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+        let program = Parser::new(source)
+            .parse()
+            .unwrap_or_else(|e| panic!("{:?}: {}", source, e));
+        let mut value = serde_json::to_value(program).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    #[test]
+    fn asi_conformance_test() {
+        // each source parses as the explicitly terminated one
+        let equivalent = [
+            ("a = 1\nb = 2", "a = 1; b = 2;"),
+            ("let x = 1\nlet y = 2", "let x = 1; let y = 2;"),
+            ("a\r\nb\u{2028}c\u{2029}d\re", "a; b; c; d; e;"),
+            ("a /* \n */ b", "a; b;"),
+            ("{ a } b", "{ a; } b;"),
+            ("{ 1\n2 } 3", "{ 1; 2; } 3;"),
+            ("if (a) b\nelse c", "if (a) b; else c;"),
+            ("do x\nwhile (a) y", "do x; while (a); y;"),
+            ("do {} while (a) y", "do {} while (a); y;"),
+            ("function f() { return\na }", "function f() { return; a; }"),
+            ("function f() { return a\n}", "function f() { return a; }"),
+            (
+                "function f() { return /*\n*/ a }",
+                "function f() { return; a; }",
+            ),
+            ("while (a) { break\nb }", "while (a) { break; b; }"),
+            ("while (a) { continue\nb }", "while (a) { continue; b; }"),
+            ("throw a\nb", "throw a; b;"),
+            ("a\n++b", "a; ++b;"),
+            ("a\n--\nb", "a; --b;"),
+            ("a++\nb", "a++; b;"),
+            ("a = b\n++c", "a = b; ++c;"),
+            // no semicolon is inserted if the next line continues the statement
+            ("a = b\n(c)", "a = b(c);"),
+            ("a = b\n[c]", "a = b[c];"),
+            ("a = b\n+ c", "a = b + c;"),
+            ("a = b\n.c", "a = b.c;"),
+            ("a = b\n/c/d", "a = b / c / d;"),
+            ("a\n=\nb", "a = b;"),
+        ];
+        for (source, expected) in equivalent {
+            assert_eq!(ast_shape(source), ast_shape(expected), "{:?}", source);
+        }
+
+        // no semicolon can be inserted
+        let invalid = [
+            "a b",
+            "a = 1 b = 2",
+            "a /* */ b",
+            "throw\na",
+            "for (a\nb) {}",
+            "for (a; b\n) {}",
+            "a\n++",
+        ];
+        for source in invalid {
+            assert!(Parser::new(source).parse().is_err(), "{:?}", source);
+        }
+        let err = Parser::new("throw\nnew_error").parse().unwrap_err();
+        assert!(matches!(err, ParseError::NewLineAfterThrow { .. }));
+    }
+
     #[test]
     fn pratt_test() {
         let source = "(a + b) + c * c";
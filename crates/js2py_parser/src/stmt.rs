@@ -10,11 +10,29 @@ impl<'a> Parser<'a> {
             if !is_top_level && self.at(Type::RCurly) {
                 break;
             }
-            statements.push(self.parse_statement(StatementContext::StatementList)?);
+            statements.push(self.parse_statement_list_item()?);
         }
 
         Ok(statements)
     }
+
+    /// Parse a statement of a statement list
+    ///
+    /// In recovering mode a statement that fails to parse is recorded as an error and replaced
+    /// by an `ErrorStatement` spanning the tokens skipped to reach the next statement.
+    pub(crate) fn parse_statement_list_item(&mut self) -> Result<Statement<'a>> {
+        let span = self.start_span();
+        match self.parse_statement(StatementContext::StatementList) {
+            Err(error) if self.recovering => {
+                self.errors.push(error);
+                self.synchronize(span);
+                Ok(Statement::ErrorStatement(Box::new(ErrorStatement {
+                    span: self.recovered_span(span),
+                })))
+            }
+            result => result,
+        }
+    }
     pub(crate) fn parse_statement(&mut self, stmt_ctx: StatementContext) -> Result<Statement<'a>> {
         match self.cur_kind() {
             Type::LCurly => self.parse_block_statement(),
@@ -26,7 +44,10 @@ impl<'a> Parser<'a> {
             Type::Break | Type::Continue => self.parse_break_or_continue_statement(),
             Type::Switch => self.parse_switch_statement(),
             Type::Return => self.parse_return_statement(),
+            Type::Throw => self.parse_throw_statement(),
+            Type::Try => self.parse_try_statement(),
             Type::Function => self.parse_function_declaration(stmt_ctx),
+            Type::Class => self.parse_class_declaration(),
             kind if kind.is_variable_declaration() => self.parse_variable_statement(stmt_ctx),
             _ => self.parse_expression_statement(),
         }
@@ -36,17 +57,64 @@ impl<'a> Parser<'a> {
         let start_span = self.start_span();
         let kind = self.cur_kind();
         self.bump_any();
+        // restricted production: no line terminator before the label
+        let label = if self.at(Type::Identifier) && !self.cur_token().is_on_new_line {
+            let (span, name) = self.parse_identifier_kind();
+            let label = LabelIdentifier { span, name };
+            match self.labels.iter().rev().find(|(l, _)| *l == name) {
+                None => {
+                    return Err(ParseError::UndefinedLabel {
+                        label: name.to_string(),
+                        span,
+                    })
+                }
+                Some((_, false)) if kind == Type::Continue => {
+                    return Err(ParseError::InvalidContinueLabel {
+                        label: name.to_string(),
+                        span,
+                    })
+                }
+                Some(_) => Some(label),
+            }
+        } else {
+            None
+        };
         self.auto_semicoclon_insertion()?;
         let span = self.end_span(start_span);
         match kind {
-            Type::Break => Ok(Statement::BreakStatement(Box::new(BreakStatement { span }))),
+            Type::Break => Ok(Statement::BreakStatement(Box::new(BreakStatement {
+                span,
+                label,
+            }))),
             Type::Continue => Ok(Statement::ContinueStatement(Box::new(ContinueStatement {
                 span,
+                label,
             }))),
             _ => unreachable!(),
         }
     }
 
+    /// Parse the statement after `label:`, the label was parsed as an identifier expression
+    fn parse_labeled_statement(
+        &mut self,
+        span: Span,
+        label: Identifier<'a>,
+    ) -> Result<Statement<'a>> {
+        self.bump_any(); // bump `:`
+        let is_loop = matches!(self.cur_kind(), Type::For | Type::While | Type::Do);
+        self.labels.push((label.name, is_loop));
+        let body = self.parse_statement(StatementContext::Label);
+        self.labels.pop();
+        Ok(Statement::LabeledStatement(Box::new(LabeledStatement {
+            span: self.end_span(span),
+            label: LabelIdentifier {
+                span: label.span,
+                name: label.name,
+            },
+            body: body?,
+        })))
+    }
+
     fn parse_do_while_statement(&mut self) -> Result<Statement<'a>> {
         let span = self.start_span();
         self.bump_any(); // advance `do`
@@ -93,11 +161,59 @@ impl<'a> Parser<'a> {
             return self.parse_for_loop(span, None);
         }
 
-        let init_expression = self.parse_expr()?;
+        let init_expression = self.with_in(false, Self::parse_expr)?;
+
+        // for (a in | for (a.b of
+        if self.at(Type::In) || self.at(Type::Of) {
+            if !matches!(
+                init_expression,
+                Expression::Identifier(_)
+                    | Expression::StaticMemberExpression(_)
+                    | Expression::ComputedMemberExpression(_)
+            ) {
+                return Err(ParseError::InvalidAssignmentTarget {
+                    span: init_expression.span(),
+                });
+            }
+            return self
+                .parse_for_in_or_of_loop(span, ForStatementLeft::Expression(init_expression));
+        }
 
         self.parse_for_loop(span, Some(ForStatementInit::Expression(init_expression)))
     }
 
+    fn parse_for_in_or_of_loop(
+        &mut self,
+        span: Span,
+        left: ForStatementLeft<'a>,
+    ) -> Result<Statement<'a>> {
+        let is_for_in = self.at(Type::In);
+        self.bump_any(); // bump `in` or `of`
+        let right = if is_for_in {
+            self.parse_expr()?
+        } else {
+            self.parse_assignment_expression_or_higher()?
+        };
+        self.expect(Type::RParen)?;
+        let body = self.parse_statement(StatementContext::For)?;
+        let span = self.end_span(span);
+        if is_for_in {
+            Ok(Statement::ForInStatement(Box::new(ForInStatement {
+                span,
+                left,
+                right,
+                body,
+            })))
+        } else {
+            Ok(Statement::ForOfStatement(Box::new(ForOfStatement {
+                span,
+                left,
+                right,
+                body,
+            })))
+        }
+    }
+
     fn parse_for_loop(
         &mut self,
         span: Span,
@@ -128,7 +244,15 @@ impl<'a> Parser<'a> {
 
     fn parse_variable_declaration_for_statement(&mut self, span: Span) -> Result<Statement<'a>> {
         let start_span = self.start_span();
-        let init_declaration = self.parse_variable_declaration(start_span)?;
+        let init_declaration = self.with_in(false, |p| p.parse_variable_declaration(start_span))?;
+        // for (const a in | for (let a of
+        if (self.at(Type::In) || self.at(Type::Of))
+            && matches!(init_declaration.declarations.as_slice(), [d] if d.init.is_none())
+        {
+            let left = ForStatementLeft::VariableDeclaration(Box::new(init_declaration));
+            return self.parse_for_in_or_of_loop(span, left);
+        }
+        self.check_initializers(&init_declaration)?;
         let init = Some(ForStatementInit::VariableDeclaration(Box::new(
             init_declaration,
         )));
@@ -153,6 +277,23 @@ impl<'a> Parser<'a> {
         })))
     }
 
+    fn parse_throw_statement(&mut self) -> Result<Statement<'a>> {
+        let span = self.start_span();
+        self.bump_any();
+        // restricted production: no line terminator after `throw`
+        if self.cur_token().is_on_new_line {
+            return Err(ParseError::NewLineAfterThrow {
+                span: self.end_span(span),
+            });
+        }
+        let argument = self.parse_expr()?;
+        self.auto_semicoclon_insertion()?;
+        Ok(Statement::ThrowStatement(Box::new(ThrowStatement {
+            span: self.end_span(span),
+            argument,
+        })))
+    }
+
     fn parse_empty_statement(&mut self) -> Statement<'a> {
         let span = self.start_span();
         self.bump_any(); // bump `;`
@@ -164,6 +305,19 @@ impl<'a> Parser<'a> {
     fn parse_expression_statement(&mut self) -> Result<Statement<'a>> {
         let start_span = self.start_span();
         let expr = self.parse_expr()?;
+        match expr {
+            Expression::Identifier(label) if self.at(Type::Colon) => {
+                self.parse_labeled_statement(start_span, *label)
+            }
+            expr => self.finish_expression_statement(start_span, expr),
+        }
+    }
+
+    fn finish_expression_statement(
+        &mut self,
+        start_span: Span,
+        expr: Expression<'a>,
+    ) -> Result<Statement<'a>> {
         self.auto_semicoclon_insertion()?;
         Ok(Statement::ExpressionStatement(Box::new(
             ExpressionStatement {
@@ -174,21 +328,69 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_block_statement(&mut self) -> Result<Statement<'a>> {
+        let block = self.parse_block()?;
+        Ok(Statement::BlockStatement(block))
+    }
+
+    fn parse_block(&mut self) -> Result<Box<BlockStatement<'a>>> {
         let span = self.start_span();
         self.expect(Type::LCurly)?;
         let mut body = vec![];
         while !self.at(Type::RCurly) && !self.at(Type::EOF) {
-            let stmt = self.parse_statement(StatementContext::StatementList)?;
+            let stmt = self.parse_statement_list_item()?;
             body.push(stmt);
         }
         self.expect(Type::RCurly)?;
 
-        Ok(Statement::BlockStatement(Box::new(BlockStatement {
+        Ok(Box::new(BlockStatement {
             span: self.end_span(span),
             body,
+        }))
+    }
+
+    fn parse_try_statement(&mut self) -> Result<Statement<'a>> {
+        let span = self.start_span();
+        self.bump_any(); // bump `try`
+        let block = self.parse_block()?;
+        let handler = self
+            .at(Type::Catch)
+            .then(|| self.parse_catch_clause())
+            .transpose()?;
+        let finalizer = self
+            .eat(Type::Finally)
+            .then(|| self.parse_block())
+            .transpose()?;
+        if handler.is_none() && finalizer.is_none() {
+            return Err(ParseError::MissingCatchOrFinally {
+                span: self.end_span(span),
+            });
+        }
+        Ok(Statement::TryStatement(Box::new(TryStatement {
+            span: self.end_span(span),
+            block,
+            handler,
+            finalizer,
         })))
     }
 
+    fn parse_catch_clause(&mut self) -> Result<Box<CatchClause<'a>>> {
+        let span = self.start_span();
+        self.bump_any(); // bump `catch`
+        let param = if self.eat(Type::LParen) {
+            let param = self.parse_identifier()?;
+            self.expect(Type::RParen)?;
+            Some(param)
+        } else {
+            None
+        };
+        let body = self.parse_block()?;
+        Ok(Box::new(CatchClause {
+            span: self.end_span(span),
+            param,
+            body,
+        }))
+    }
+
     fn parse_if_statement(&mut self) -> Result<Statement<'a>> {
         let span = self.start_span();
         self.bump_any(); // bump `if`
@@ -239,7 +441,7 @@ impl<'a> Parser<'a> {
             self.cur_kind(),
             Type::Case | Type::Default | Type::RCurly | Type::EOF
         ) {
-            let stmt = self.parse_statement(StatementContext::StatementList)?;
+            let stmt = self.parse_statement_list_item()?;
             consequent.push(stmt);
         }
         Ok(Some(SwitchCase {
@@ -283,27 +485,55 @@ impl<'a> Parser<'a> {
             _ => return Err(self.unexpected()),
         };
         self.bump_any();
-        // self.bump(Type::Var);
 
-        let id = self.parse_identifier()?;
+        let mut declarations = vec![self.parse_variable_declarator()?];
+        while self.eat(Type::Comma) {
+            declarations.push(self.parse_variable_declarator()?);
+        }
+
+        Ok(VariableDeclaration {
+            span: self.end_span(start_span),
+            kind,
+            declarations,
+        })
+    }
 
+    fn parse_variable_declarator(&mut self) -> Result<VariableDeclarator<'a>> {
+        let span = self.start_span();
+        let id = self.parse_binding_pattern()?;
         let init = self
             .eat(Type::Eq)
             .then(|| self.parse_assignment_expression_or_higher())
             .transpose()?;
-
-        Ok(VariableDeclaration {
-            span: self.end_span(start_span),
-            kind,
+        Ok(VariableDeclarator {
+            span: self.end_span(span),
             id,
             init,
         })
     }
 
+    /// Destructuring patterns and `const` declarations must be initialized, except in the head
+    /// of a for-in or for-of loop
+    fn check_initializers(&self, decl: &VariableDeclaration<'a>) -> Result<()> {
+        let is_const = matches!(decl.kind, VariableDeclarationKind::Const);
+        for declarator in &decl.declarations {
+            let is_pattern = !matches!(declarator.id, BindingPattern::BindingIdentifier(_));
+            if declarator.init.is_none() && (is_const || is_pattern) {
+                return Err(ParseError::MissingInitializer {
+                    span: declarator.span,
+                });
+            }
+        }
+        Ok(())
+    }
+
     #[allow(unused_variables)]
     fn parse_variable_statement(&mut self, stmt_ctx: StatementContext) -> Result<Statement<'a>> {
         let start_span = self.start_span();
-        let decl = self.parse_variable_declaration(start_span)?;
+        let mut decl = self.parse_variable_declaration(start_span)?;
+        self.check_initializers(&decl)?;
+        self.auto_semicoclon_insertion()?;
+        decl.span = self.end_span(start_span);
         Ok(Statement::VariableDeclarationStatement(Box::new(decl)))
     }
 }
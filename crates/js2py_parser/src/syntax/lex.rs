@@ -18,6 +18,7 @@ pub trait TokenTypeUtil {
     fn is_all_keyword(self) -> bool;
     fn is_variable_declaration(self) -> bool;
     fn is_assignment_operator(self) -> bool;
+    fn is_statement_start(self) -> bool;
 }
 
 impl TokenTypeUtil for Type {
@@ -26,23 +27,23 @@ impl TokenTypeUtil for Type {
     }
 
     fn is_number(self) -> bool {
-        matches!(self, Decimal | Hex)
+        matches!(self, Decimal | Hex | Binary | Octal | BigInt)
     }
 
     fn is_logical_operator(self) -> bool {
-        matches!(self, Pipe2 | Amp2)
+        matches!(self, Pipe2 | Amp2 | Question2)
     }
     #[rustfmt::skip]
     fn is_binary_operator(self) -> bool {
         matches!(self, Eq2 | Neq | Eq3 | Neq2 | LAngle | LtEq | RAngle | GtEq | ShiftLeft | ShiftRight
             | ShiftRight3 | Plus | Minus | Star | Slash | Percent | Pipe | Caret | Amp | In
-            | Instanceof)
+            | Instanceof | Star2)
     }
     fn is_unary_operator(self) -> bool {
         matches!(self, Minus | Plus | Bang | Tilde | Typeof | Void | Delete)
     }
     fn is_literal(self) -> bool {
-        matches!(self, Null | True | False | Str) || self.is_number()
+        matches!(self, Null | True | False | Str | RegExp) || self.is_number()
     }
 
     fn is_identifier(self) -> bool {
@@ -88,6 +89,12 @@ impl TokenTypeUtil for Type {
     }
     #[rustfmt::skip]
     fn is_assignment_operator(self) -> bool {
-        matches!(self, Eq | PlusEq | MinusEq | StarEq | SlashEq | PercentEq | ShiftLeftEq | ShiftRightEq | ShiftRight3Eq | AmpEq | CaretEq | PipeEq)
+        matches!(self, Eq | PlusEq | MinusEq | StarEq | SlashEq | PercentEq | ShiftLeftEq | ShiftRightEq | ShiftRight3Eq | AmpEq | CaretEq | PipeEq
+            | Star2Eq | Amp2Eq | Pipe2Eq | Question2Eq)
+    }
+    #[rustfmt::skip]
+    fn is_statement_start(self) -> bool {
+        matches!(self, Var | Let | Const | Function | Class | If | For | While | Do | Return | Break
+            | Continue | Switch | Throw | Try | With | Debugger | Import | Export)
     }
 }
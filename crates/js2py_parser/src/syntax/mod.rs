@@ -1,3 +1,4 @@
 pub mod lex;
+pub mod number;
 pub mod operator;
 pub mod precedence;
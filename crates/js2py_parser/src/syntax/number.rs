@@ -0,0 +1,45 @@
+use js2py_lexer::token::Type;
+
+/// Compute the value of a numeric literal token
+///
+/// * `raw`: the source text of the token, including separators and prefixes
+/// * `kind`: the type of the token
+pub fn parse_number(raw: &str, kind: Type) -> Option<f64> {
+    let text = raw.strip_suffix('n').unwrap_or(raw);
+    let radix = match text.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    match (kind, radix) {
+        (Type::Decimal | Type::BigInt, 10) => parse_decimal(text),
+        (Type::Hex | Type::Octal | Type::Binary | Type::BigInt, _) => parse_int(&text[2..], radix),
+        _ => None,
+    }
+}
+
+/// Parse the digits of a non-decimal integer, e.g. `FF_FF` in `0xFF_FF`
+fn parse_int(digits: &str, radix: u32) -> Option<f64> {
+    let mut value = 0f64;
+    let mut has_digit = false;
+    for c in digits.chars().filter(|&c| c != '_') {
+        value = value * radix as f64 + c.to_digit(radix)? as f64;
+        has_digit = true;
+    }
+    has_digit.then_some(value)
+}
+
+/// Parse a decimal literal, e.g. `1_000.5e-3` or `.5`
+fn parse_decimal(text: &str) -> Option<f64> {
+    let mut digits = String::with_capacity(text.len() + 1);
+    for c in text.chars() {
+        match c {
+            '0'..='9' | '.' | 'e' | 'E' | '+' | '-' => digits.push(c),
+            '_' => {}
+            _ => return None,
+        }
+    }
+    // the exactly rounded conversion is left to the standard library
+    digits.parse::<f64>().ok()
+}
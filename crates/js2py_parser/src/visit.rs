@@ -0,0 +1,308 @@
+//! Read-only traversal of the AST.
+//!
+//! Implement [`Visit`] and override the methods of the nodes of interest, an overriding method
+//! calls the matching `walk_*` function to keep visiting the children.
+
+use crate::ast::*;
+
+pub trait Visit<'a> {
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        walk_statement(self, stmt);
+    }
+
+    fn visit_expression(&mut self, expr: &Expression<'a>) {
+        walk_expression(self, expr);
+    }
+
+    fn visit_function(&mut self, function: &Function<'a>) {
+        walk_function(self, function);
+    }
+
+    fn visit_arrow_function(&mut self, arrow: &ArrowFunctionExpression<'a>) {
+        walk_arrow_function(self, arrow);
+    }
+
+    fn visit_class(&mut self, class: &Class<'a>) {
+        walk_class(self, class);
+    }
+
+    fn visit_variable_declaration(&mut self, var: &VariableDeclaration<'a>) {
+        walk_variable_declaration(self, var);
+    }
+
+    fn visit_binding_pattern(&mut self, pattern: &BindingPattern<'a>) {
+        walk_binding_pattern(self, pattern);
+    }
+
+    fn visit_assignment_target(&mut self, target: &AssignmentTarget<'a>) {
+        walk_assignment_target(self, target);
+    }
+
+    fn visit_for_statement_left(&mut self, left: &ForStatementLeft<'a>) {
+        walk_for_statement_left(self, left);
+    }
+}
+
+pub fn walk_statements<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, stmts: &[Statement<'a>]) {
+    for stmt in stmts {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_statement<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, stmt: &Statement<'a>) {
+    match stmt {
+        Statement::BlockStatement(block) => walk_statements(visitor, &block.body),
+        Statement::IfStatement(if_stmt) => {
+            visitor.visit_expression(&if_stmt.test);
+            visitor.visit_statement(&if_stmt.consequent);
+            if let Some(alternate) = &if_stmt.alternate {
+                visitor.visit_statement(alternate);
+            }
+        }
+        Statement::ExpressionStatement(e) => visitor.visit_expression(&e.expression),
+        Statement::ReturnStatement(r) => {
+            if let Some(argument) = &r.argument {
+                visitor.visit_expression(argument);
+            }
+        }
+        Statement::ThrowStatement(t) => visitor.visit_expression(&t.argument),
+        Statement::TryStatement(t) => {
+            walk_statements(visitor, &t.block.body);
+            if let Some(handler) = &t.handler {
+                walk_statements(visitor, &handler.body.body);
+            }
+            if let Some(finalizer) = &t.finalizer {
+                walk_statements(visitor, &finalizer.body);
+            }
+        }
+        Statement::ForStatement(for_stmt) => {
+            match &for_stmt.init {
+                Some(ForStatementInit::VariableDeclaration(var)) => {
+                    visitor.visit_variable_declaration(var)
+                }
+                Some(ForStatementInit::Expression(init)) => visitor.visit_expression(init),
+                None => {}
+            }
+            if let Some(test) = &for_stmt.test {
+                visitor.visit_expression(test);
+            }
+            if let Some(update) = &for_stmt.update {
+                visitor.visit_expression(update);
+            }
+            visitor.visit_statement(&for_stmt.body);
+        }
+        Statement::ForInStatement(for_in) => {
+            visitor.visit_for_statement_left(&for_in.left);
+            visitor.visit_expression(&for_in.right);
+            visitor.visit_statement(&for_in.body);
+        }
+        Statement::ForOfStatement(for_of) => {
+            visitor.visit_for_statement_left(&for_of.left);
+            visitor.visit_expression(&for_of.right);
+            visitor.visit_statement(&for_of.body);
+        }
+        Statement::WhileStatement(while_stmt) => {
+            visitor.visit_expression(&while_stmt.test);
+            visitor.visit_statement(&while_stmt.body);
+        }
+        Statement::DoWhileStatement(do_while) => {
+            visitor.visit_statement(&do_while.body);
+            visitor.visit_expression(&do_while.test);
+        }
+        Statement::SwitchStatement(switch) => {
+            visitor.visit_expression(&switch.discriminant);
+            for case in &switch.cases {
+                if let Some(test) = &case.test {
+                    visitor.visit_expression(test);
+                }
+                walk_statements(visitor, &case.consequent);
+            }
+        }
+        Statement::WithStatement(with) => {
+            visitor.visit_expression(&with.object);
+            visitor.visit_statement(&with.body);
+        }
+        Statement::LabeledStatement(labeled) => visitor.visit_statement(&labeled.body),
+        Statement::VariableDeclarationStatement(var) => visitor.visit_variable_declaration(var),
+        Statement::FunctionDeclaration(function) => visitor.visit_function(function),
+        Statement::ClassDeclaration(class) => visitor.visit_class(class),
+        Statement::EmptyStatement(_)
+        | Statement::BreakStatement(_)
+        | Statement::ContinueStatement(_)
+        | Statement::ErrorStatement(_) => {}
+    }
+}
+
+pub fn walk_expression<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, expr: &Expression<'a>) {
+    match expr {
+        Expression::TemplateLiteral(template) => {
+            for expression in &template.expressions {
+                visitor.visit_expression(expression);
+            }
+        }
+        Expression::SequenceExpression(seq) => {
+            for expression in &seq.expressions {
+                visitor.visit_expression(expression);
+            }
+        }
+        Expression::BinaryExpression(bin) => {
+            visitor.visit_expression(&bin.left);
+            visitor.visit_expression(&bin.right);
+        }
+        Expression::LogicalExpression(logic) => {
+            visitor.visit_expression(&logic.left);
+            visitor.visit_expression(&logic.right);
+        }
+        Expression::ConditionalExpression(cond) => {
+            visitor.visit_expression(&cond.test);
+            visitor.visit_expression(&cond.consequent);
+            visitor.visit_expression(&cond.alternate);
+        }
+        Expression::UnaryExpression(unary) => visitor.visit_expression(&unary.argument),
+        Expression::UpdateExpression(update) => visitor.visit_expression(&update.argument),
+        Expression::AssignmentExpression(assign) => {
+            visitor.visit_assignment_target(&assign.left);
+            visitor.visit_expression(&assign.right);
+        }
+        Expression::ArrayExpression(array) => {
+            for element in &array.elements {
+                if let ArrayExpressionElement::Expression(element) = element {
+                    visitor.visit_expression(element);
+                }
+            }
+        }
+        Expression::ObjectExpression(object) => {
+            for property in &object.properties {
+                visitor.visit_expression(&property.value);
+            }
+        }
+        Expression::StaticMemberExpression(member) => visitor.visit_expression(&member.object),
+        Expression::ComputedMemberExpression(member) => {
+            visitor.visit_expression(&member.object);
+            visitor.visit_expression(&member.expression);
+        }
+        Expression::CallExpression(call) => {
+            visitor.visit_expression(&call.callee);
+            for argument in &call.arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        Expression::NewExpression(new) => {
+            visitor.visit_expression(&new.callee);
+            for argument in &new.arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        Expression::ChainExpression(chain) => visitor.visit_expression(&chain.expression),
+        Expression::ArrowFunctionExpression(arrow) => visitor.visit_arrow_function(arrow),
+        Expression::FunctionExpression(function) => visitor.visit_function(function),
+        Expression::ClassExpression(class) => visitor.visit_class(class),
+        Expression::ParenthesizedExpression(paren) => visitor.visit_expression(&paren.expression),
+        Expression::BooleanLiteral(_)
+        | Expression::NullLiteral(_)
+        | Expression::NumericLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::RegExpLiteral(_)
+        | Expression::Identifier(_)
+        | Expression::ThisExpression(_)
+        | Expression::Super(_)
+        | Expression::MetaProperty(_)
+        | Expression::ErrorExpression(_) => {}
+    }
+}
+
+pub fn walk_function<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, function: &Function<'a>) {
+    if let Some(body) = &function.body {
+        walk_statements(visitor, &body.statements);
+    }
+}
+
+pub fn walk_arrow_function<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    arrow: &ArrowFunctionExpression<'a>,
+) {
+    walk_statements(visitor, &arrow.body.statements);
+}
+
+pub fn walk_class<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, class: &Class<'a>) {
+    if let Some(super_class) = &class.super_class {
+        visitor.visit_expression(super_class);
+    }
+    for element in &class.body {
+        match element {
+            ClassElement::MethodDefinition(method) => visitor.visit_function(&method.value),
+            ClassElement::PropertyDefinition(property) => {
+                if let Some(value) = &property.value {
+                    visitor.visit_expression(value);
+                }
+            }
+        }
+    }
+}
+
+pub fn walk_variable_declaration<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    var: &VariableDeclaration<'a>,
+) {
+    for declarator in &var.declarations {
+        visitor.visit_binding_pattern(&declarator.id);
+        if let Some(init) = &declarator.init {
+            visitor.visit_expression(init);
+        }
+    }
+}
+
+pub fn walk_binding_pattern<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    pattern: &BindingPattern<'a>,
+) {
+    match pattern {
+        BindingPattern::BindingIdentifier(_) => {}
+        BindingPattern::ObjectPattern(object) => {
+            for property in &object.properties {
+                visitor.visit_binding_pattern(&property.value);
+            }
+            if let Some(rest) = &object.rest {
+                visitor.visit_binding_pattern(&rest.argument);
+            }
+        }
+        BindingPattern::ArrayPattern(array) => {
+            for element in array.elements.iter().flatten() {
+                visitor.visit_binding_pattern(element);
+            }
+            if let Some(rest) = &array.rest {
+                visitor.visit_binding_pattern(&rest.argument);
+            }
+        }
+        BindingPattern::AssignmentPattern(assign) => {
+            visitor.visit_binding_pattern(&assign.left);
+            visitor.visit_expression(&assign.right);
+        }
+    }
+}
+
+pub fn walk_assignment_target<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    target: &AssignmentTarget<'a>,
+) {
+    match target {
+        AssignmentTarget::Identifier(_) => {}
+        AssignmentTarget::StaticMemberExpression(member) => {
+            visitor.visit_expression(&member.object)
+        }
+        AssignmentTarget::ComputedMemberExpression(member) => {
+            visitor.visit_expression(&member.object);
+            visitor.visit_expression(&member.expression);
+        }
+    }
+}
+
+pub fn walk_for_statement_left<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    left: &ForStatementLeft<'a>,
+) {
+    match left {
+        ForStatementLeft::VariableDeclaration(var) => visitor.visit_variable_declaration(var),
+        ForStatementLeft::Expression(expr) => visitor.visit_expression(expr),
+    }
+}
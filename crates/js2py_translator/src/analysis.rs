@@ -0,0 +1,260 @@
+//! Questions about the JavaScript code that decide between translations.
+
+use js2py_parser::ast::*;
+use js2py_parser::visit::{
+    walk_binding_pattern, walk_expression, walk_for_statement_left, walk_statement,
+    walk_statements, Visit,
+};
+use std::collections::HashSet;
+
+/// Whether the variable `name` is assigned to or updated anywhere in `stmt`, including the
+/// functions defined in it
+pub(crate) fn is_written(stmt: &Statement, name: &str) -> bool {
+    let mut finder = WriteFinder { name, found: false };
+    finder.visit_statement(stmt);
+    finder.found
+}
+
+/// Whether the variable `name` is read anywhere in `stmts`, including the functions defined in it
+pub(crate) fn is_read(stmts: &[Statement], name: &str) -> bool {
+    let mut finder = ReadFinder { name, found: false };
+    walk_statements(&mut finder, stmts);
+    finder.found
+}
+
+struct ReadFinder<'n> {
+    name: &'n str,
+    found: bool,
+}
+
+impl<'a> Visit<'a> for ReadFinder<'_> {
+    fn visit_expression(&mut self, expr: &Expression<'a>) {
+        if matches!(expr, Expression::Identifier(id) if id.name == self.name) {
+            self.found = true;
+        }
+        walk_expression(self, expr);
+    }
+}
+
+/// Whether `this` is used in `expr`, including the arrow functions in it, which share the
+/// `this` of the enclosing code
+pub(crate) fn uses_this(expr: &Expression) -> bool {
+    let mut finder = ThisFinder { found: false };
+    finder.visit_expression(expr);
+    finder.found
+}
+
+struct ThisFinder {
+    found: bool,
+}
+
+impl<'a> Visit<'a> for ThisFinder {
+    fn visit_expression(&mut self, expr: &Expression<'a>) {
+        if matches!(expr, Expression::ThisExpression(_)) {
+            self.found = true;
+        }
+        walk_expression(self, expr);
+    }
+
+    fn visit_function(&mut self, _function: &Function<'a>) {}
+
+    fn visit_class(&mut self, _class: &Class<'a>) {}
+}
+
+struct WriteFinder<'n> {
+    name: &'n str,
+    found: bool,
+}
+
+impl<'a> Visit<'a> for WriteFinder<'_> {
+    fn visit_expression(&mut self, expr: &Expression<'a>) {
+        match expr {
+            Expression::AssignmentExpression(assign) => {
+                if matches!(&assign.left, AssignmentTarget::Identifier(id) if id.name == self.name)
+                {
+                    self.found = true;
+                }
+            }
+            Expression::UpdateExpression(update) => {
+                if matches!(&update.argument, Expression::Identifier(id) if id.name == self.name) {
+                    self.found = true;
+                }
+            }
+            _ => {}
+        }
+        walk_expression(self, expr);
+    }
+
+    fn visit_binding_pattern(&mut self, pattern: &BindingPattern<'a>) {
+        if matches!(pattern, BindingPattern::BindingIdentifier(id) if id.name == self.name) {
+            self.found = true;
+        }
+        walk_binding_pattern(self, pattern);
+    }
+
+    fn visit_for_statement_left(&mut self, left: &ForStatementLeft<'a>) {
+        if matches!(left, ForStatementLeft::Expression(Expression::Identifier(id)) if id.name == self.name)
+        {
+            self.found = true;
+        }
+        walk_for_statement_left(self, left);
+    }
+}
+
+/// Whether a `break` in `stmts` jumps out of the statement list, to the enclosing `switch` or
+/// loop, rather than out of a loop or `switch` nested in it
+pub(crate) fn breaks_out(stmts: &[Statement]) -> bool {
+    let mut finder = BreakFinder { found: false };
+    for stmt in stmts {
+        finder.visit_statement(stmt);
+    }
+    finder.found
+}
+
+struct BreakFinder {
+    found: bool,
+}
+
+impl<'a> Visit<'a> for BreakFinder {
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        match stmt {
+            // a labeled `break` jumps to its label
+            Statement::BreakStatement(b) => self.found |= b.label.is_none(),
+            Statement::ForStatement(_)
+            | Statement::ForInStatement(_)
+            | Statement::ForOfStatement(_)
+            | Statement::WhileStatement(_)
+            | Statement::DoWhileStatement(_)
+            | Statement::SwitchStatement(_)
+            | Statement::FunctionDeclaration(_)
+            | Statement::ClassDeclaration(_) => {}
+            _ => walk_statement(self, stmt),
+        }
+    }
+
+    // a `break` in an expression is in a function, it cannot jump out of it
+    fn visit_expression(&mut self, _expr: &Expression<'a>) {}
+}
+
+/// Whether a `break` or `continue` in `stmt` jumps to `label`
+pub(crate) fn uses_label(stmt: &Statement, label: &str) -> bool {
+    let mut finder = LabelFinder {
+        label,
+        found: false,
+    };
+    finder.visit_statement(stmt);
+    finder.found
+}
+
+struct LabelFinder<'l> {
+    label: &'l str,
+    found: bool,
+}
+
+impl<'a> Visit<'a> for LabelFinder<'_> {
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        let label = match stmt {
+            Statement::BreakStatement(b) => b.label.as_ref(),
+            Statement::ContinueStatement(c) => c.label.as_ref(),
+            _ => None,
+        };
+        if label.is_some_and(|label| label.name == self.label) {
+            self.found = true;
+        }
+        walk_statement(self, stmt);
+    }
+
+    // labels are not visible in functions
+    fn visit_expression(&mut self, _expr: &Expression<'a>) {}
+}
+
+/// The variables of a function body, not counting the functions nested in it
+#[derive(Default)]
+pub(crate) struct FunctionScope<'a> {
+    /// The variables declared by the function, including its parameters
+    pub declared: HashSet<&'a str>,
+    /// The variables assigned by the function, in the order of their first assignment
+    pub assigned: Vec<&'a str>,
+}
+
+impl<'a> FunctionScope<'a> {
+    pub fn new(params: &FormalParameters<'a>, body: &[Statement<'a>]) -> Self {
+        let mut scope = Self::default();
+        scope
+            .declared
+            .extend(params.params.iter().map(|param| param.name));
+        walk_statements(&mut scope, body);
+        scope
+    }
+
+    /// The variables assigned by the function that belong to an enclosing scope
+    pub fn captured(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.assigned
+            .iter()
+            .copied()
+            .filter(|name| !self.declared.contains(name))
+    }
+
+    fn assign(&mut self, name: &'a str) {
+        if !self.assigned.contains(&name) {
+            self.assigned.push(name);
+        }
+    }
+}
+
+impl<'a> Visit<'a> for FunctionScope<'a> {
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        match stmt {
+            Statement::FunctionDeclaration(function) => {
+                self.declared.extend(function.id.as_ref().map(|id| id.name));
+            }
+            Statement::ClassDeclaration(class) => {
+                self.declared.extend(class.id.as_ref().map(|id| id.name));
+            }
+            Statement::TryStatement(t) => {
+                let param = t.handler.as_ref().and_then(|h| h.param.as_ref());
+                self.declared.extend(param.map(|param| param.name));
+                walk_statement(self, stmt);
+            }
+            _ => walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &Expression<'a>) {
+        match expr {
+            Expression::AssignmentExpression(assign) => {
+                if let AssignmentTarget::Identifier(id) = &assign.left {
+                    self.assign(id.name);
+                }
+            }
+            Expression::UpdateExpression(update) => {
+                if let Expression::Identifier(id) = &update.argument {
+                    self.assign(id.name);
+                }
+            }
+            _ => {}
+        }
+        walk_expression(self, expr);
+    }
+
+    // nested functions and classes have scopes of their own
+    fn visit_function(&mut self, _function: &Function<'a>) {}
+
+    fn visit_arrow_function(&mut self, _arrow: &ArrowFunctionExpression<'a>) {}
+
+    fn visit_class(&mut self, _class: &Class<'a>) {}
+
+    fn visit_binding_pattern(&mut self, pattern: &BindingPattern<'a>) {
+        if let BindingPattern::BindingIdentifier(id) = pattern {
+            self.declared.insert(id.name);
+        }
+        walk_binding_pattern(self, pattern);
+    }
+
+    fn visit_for_statement_left(&mut self, left: &ForStatementLeft<'a>) {
+        if let ForStatementLeft::Expression(Expression::Identifier(id)) = left {
+            self.assign(id.name);
+        }
+        walk_for_statement_left(self, left);
+    }
+}
@@ -1,14 +1,131 @@
 use super::*;
+use crate::analysis::{breaks_out, is_read, is_written, uses_label, uses_this, FunctionScope};
+use crate::mangle::mangle;
+use crate::runtime;
+use js2py_parser::line_index::{LineIndex, Location};
 use js2py_parser::{ast::*, syntax::operator::*};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashSet};
 use std::sync::Arc;
 
 pub struct Ast2PyReturn {
     pub code: String,
+    /// Constructs that were translated with a different meaning in Python
+    pub warnings: Vec<String>,
+}
+
+/// The translation of an arrow function
+enum ArrowFunction {
+    /// A whole `lambda` expression
+    Lambda(String),
+    /// The body of a `def`
+    Def(String),
+}
+
+/// What `this` refers to in the function being translated
+#[derive(Clone)]
+enum ThisBinding {
+    /// Outside of methods, the global object
+    Global,
+    /// In a method or in the constructor of an instance
+    Instance { constructor: bool },
+    /// In a static method, the class with this name
+    Class(String),
+}
+
+/// A translated construct that `break` and `continue` jump out of, a Python loop
+struct Breakable {
+    kind: BreakableKind,
+    label: Option<String>,
+    /// The flags of the jumps to this construct from nested loops, and whether each one is a
+    /// `continue`
+    flags: Vec<(String, bool)>,
+    /// The jumps that leave this construct for an enclosing one, checked right after it
+    exits: Vec<Exit>,
+}
+
+enum BreakableKind {
+    /// A loop, `continue` has to run `update` first
+    Loop { update: Option<String> },
+    /// A `switch` translated to a `while True` loop
+    Switch,
+    /// A labeled statement translated to a `while True` loop, so that `break` can leave it
+    Block,
+}
+
+/// A `break` or `continue` that leaves nested loops by setting a flag and breaking out
+#[derive(Clone)]
+struct Exit {
+    flag: String,
+    /// The index of the target in the stack of breakables
+    target: usize,
+    is_continue: bool,
+}
+
+/// Consecutive cases of a `switch` that share their statements
+struct SwitchGroup<'s, 'a> {
+    /// The tests of the cases, `None` is the `default` case
+    tests: Vec<Option<&'s Expression<'a>>>,
+    consequent: &'s [Statement<'a>],
+}
+
+impl<'s, 'a> SwitchGroup<'s, 'a> {
+    fn is_default(&self) -> bool {
+        self.tests.iter().any(Option::is_none)
+    }
+
+    /// Whether the statements of the group leave the `switch` instead of falling through
+    fn is_terminated(&self) -> bool {
+        matches!(
+            self.consequent.last(),
+            Some(
+                Statement::BreakStatement(_)
+                    | Statement::ReturnStatement(_)
+                    | Statement::ThrowStatement(_)
+                    | Statement::ContinueStatement(_)
+            )
+        )
+    }
+
+    /// The statements without the `break` that ends them
+    fn body(&self) -> &'s [Statement<'a>] {
+        match self.consequent {
+            [body @ .., Statement::BreakStatement(b)] if b.label.is_none() => body,
+            body => body,
+        }
+    }
 }
 
 pub struct Ast2Py {
     source: Arc<str>,
+    line_index: LineIndex,
     indent: usize,
+    /// Whether `for...in` over a list yields its indices as strings like in JavaScript
+    string_keys: bool,
+    /// Python modules used by the translated code
+    imports: RefCell<BTreeSet<&'static str>>,
+    /// Definitions from [`runtime`] used by the translated code
+    runtime: RefCell<BTreeSet<&'static str>>,
+    /// Names bound by the enclosing `catch` clauses
+    catch_params: RefCell<Vec<String>>,
+    /// Classes extending `Error`, their instances are raised as is
+    error_classes: RefCell<HashSet<String>>,
+    /// Variables declared by the enclosing functions, innermost last
+    scopes: RefCell<Vec<HashSet<String>>>,
+    /// The enclosing constructs that `break` and `continue` jump out of, innermost last
+    breakables: RefCell<Vec<Breakable>>,
+    /// The last construct left, whose flags are placed around the statement being translated
+    left_breakable: RefCell<Option<Breakable>>,
+    /// The label of the loop about to be translated
+    loop_label: RefCell<Option<String>>,
+    this_binding: RefCell<ThisBinding>,
+    warnings: RefCell<Vec<String>>,
+    /// Lines to place before the statement being translated
+    hoisted: RefCell<Vec<String>>,
+    /// Expressions, by span, already evaluated into a temporary that is read instead
+    substitutions: RefCell<Vec<(Span, String)>>,
+    /// Number of names generated so far
+    generated_names: Cell<usize>,
 }
 
 impl Default for Ast2Py {
@@ -21,17 +138,56 @@ impl Ast2Py {
     pub fn new() -> Self {
         Self {
             source: "".into(),
+            line_index: LineIndex::default(),
             indent: 4,
+            string_keys: false,
+            imports: RefCell::default(),
+            runtime: RefCell::default(),
+            catch_params: RefCell::default(),
+            error_classes: RefCell::default(),
+            scopes: RefCell::default(),
+            breakables: RefCell::default(),
+            left_breakable: RefCell::default(),
+            loop_label: RefCell::default(),
+            this_binding: RefCell::new(ThisBinding::Global),
+            warnings: RefCell::default(),
+            hoisted: RefCell::default(),
+            substitutions: RefCell::default(),
+            generated_names: Cell::default(),
         }
     }
     pub fn with_indent(mut self, indent: usize) -> Self {
         self.indent = indent;
         self
     }
+    /// Make `for...in` loops over a list or a string yield the indices as strings like in
+    /// JavaScript, instead of integers that can index the list
+    pub fn with_string_keys(mut self, string_keys: bool) -> Self {
+        self.string_keys = string_keys;
+        self
+    }
     pub fn build(mut self, p: &Program) -> Ast2PyReturn {
         self.source = p.source_text.into();
-        let code = self.translate_program(p);
-        Ast2PyReturn { code }
+        self.line_index = LineIndex::new(p.source_text);
+        let mut code = self.translate_program(p);
+        let runtime = self.runtime.take();
+        if !runtime.is_empty() {
+            let runtime = runtime.into_iter().collect::<Vec<_>>().join("\n\n");
+            code = format!("{}\n\n{}", runtime, code);
+        }
+        let imports = self.imports.take();
+        if !imports.is_empty() {
+            let imports = imports
+                .iter()
+                .map(|module| format!("import {}", module))
+                .collect::<Vec<_>>()
+                .join("\n");
+            code = format!("{}\n\n{}", imports, code);
+        }
+        Ast2PyReturn {
+            code,
+            warnings: self.warnings.take(),
+        }
     }
 }
 
@@ -41,25 +197,102 @@ impl Ast2Py {
         &self.source[node.span().start..node.span().end]
     }
 
+    fn import(&self, module: &'static str) {
+        self.imports.borrow_mut().insert(module);
+    }
+
+    fn use_runtime(&self, definition: &'static str) {
+        self.runtime.borrow_mut().insert(definition);
+    }
+
+    fn location_of(&self, node: &impl GetSpan) -> Location {
+        self.line_index.location(node.span().start)
+    }
+
+    /// Abort on a construct that cannot be translated
+    fn unsupported(&self, node: &impl GetSpan, what: &str) -> ! {
+        unimplemented!(
+            "{}: unsupported {} {:?}",
+            self.location_of(node),
+            what,
+            self.source_of(node)
+        )
+    }
+
+    fn warn(&self, node: &impl GetSpan, msg: &str) {
+        let warning = format!(
+            "{}: {}: {}",
+            self.location_of(node),
+            self.source_of(node),
+            msg
+        );
+        self.warnings.borrow_mut().push(warning);
+    }
+
+    /// Place code before the statement being translated
+    fn hoist(&self, code: String) {
+        self.hoisted.borrow_mut().push(code);
+    }
+
+    /// A name for generated code, unique in the translated program
+    fn generate_name(&self, prefix: &str) -> String {
+        let n = self.generated_names.get() + 1;
+        self.generated_names.set(n);
+        format!("{}_{}", prefix, n)
+    }
+
+    /// Comment out the source of a node that failed to parse or cannot be translated
+    fn todo_comment(&self, node: &impl GetSpan, reason: &str) -> String {
+        let mut lines = vec![format!("# TODO: {}", reason)];
+        for line in self.source_of(node).trim_end().lines() {
+            lines.push(format!("# {}", line).trim_end().to_string());
+        }
+        lines.join("\n")
+    }
+
     // translate functions:
     fn translate_program(&self, program: &Program) -> String {
         self.translate_statements(&program.body)
     }
 
-    #[rustfmt::skip]
     fn translate_statement(&self, statement: &Statement) -> String {
+        // statements nested in this one place their hoisted code before themselves
+        let outer = self.hoisted.take();
+        let mut code = self.translate_statement_kind(statement);
+        if let Some(breakable) = self.left_breakable.take() {
+            code = self.translate_flags(breakable, code);
+        }
+        let hoisted = self.hoisted.replace(outer);
+        if hoisted.is_empty() {
+            return code;
+        }
+        format!("{}\n{}", hoisted.join("\n"), code)
+    }
+
+    #[rustfmt::skip]
+    fn translate_statement_kind(&self, statement: &Statement) -> String {
         match statement {
             Statement::BlockStatement(b) => self.translate_block_statement(b),
             Statement::IfStatement(i) => self.translate_if_statement(i),
             Statement::EmptyStatement(_) => "".into(),
             Statement::FunctionDeclaration(f) => self.translate_function(f),
+            Statement::ClassDeclaration(c) => self.translate_class_declaration(c),
             Statement::ReturnStatement(r) => self.translate_return_statement(r),
+            Statement::ThrowStatement(t) => self.translate_throw_statement(t),
+            Statement::TryStatement(t) => self.translate_try_statement(t),
             Statement::VariableDeclarationStatement(v) => self.translate_variable_declaration(v),
             Statement::WhileStatement(w) => self.translate_while_statement(w),
-            Statement::ExpressionStatement(e) => self.translate_expression(&e.expression),
-            Statement::ContinueStatement(_) => String::from("continue"),
-            Statement::BreakStatement(_) => String::from("break"),
-            _ => unimplemented!("unsupported statement {:?}", self.source_of(statement)),
+            Statement::DoWhileStatement(d) => self.translate_do_while_statement(d),
+            Statement::ForStatement(f) => self.translate_for_statement(f),
+            Statement::ForInStatement(f) => self.translate_for_in_statement(f),
+            Statement::SwitchStatement(s) => self.translate_switch_statement(s),
+            Statement::ForOfStatement(f) => self.translate_for_of_statement(f),
+            Statement::ExpressionStatement(e) => self.translate_expression_statement(&e.expression),
+            Statement::ContinueStatement(c) => self.translate_jump(c.label.as_ref(), true),
+            Statement::BreakStatement(b) => self.translate_jump(b.label.as_ref(), false),
+            Statement::LabeledStatement(l) => self.translate_labeled_statement(l),
+            Statement::ErrorStatement(e) => self.todo_comment(e.as_ref(), "syntax error"),
+            _ => self.unsupported(statement, "statement"),
         }
     }
 
@@ -101,227 +334,2232 @@ impl Ast2Py {
     }
 
     fn translate_while_statement(&self, while_stmt: &WhileStatement) -> String {
-        let test = self.translate_expression(&while_stmt.test);
-        let body = self
-            .translate_statement(&while_stmt.body)
-            .with_placeholder("pass");
+        let label = self.loop_label.take();
+        let (test, check) = self.translate_loop_test(&while_stmt.test);
+        let mut body = vec![check];
+        body.push(self.translate_loop_body(&while_stmt.body, None, label));
+        body.retain(|code| !code.is_empty());
+        let body = body.join("\n").with_placeholder("pass");
         format!("while {}:\n{}", test, make_indent(&body, self.indent))
     }
 
-    fn translate_variable_declaration(&self, var: &VariableDeclaration) -> String {
-        let name = var.id.name.to_string();
-        let value = self.translate_expression(var.init.as_ref().unwrap());
-        format!("{} = {}", name, value)
+    /// The test of a `while` loop, and the code to check it with at the start of every
+    /// iteration if it hoists code, which must run each time the test is evaluated, in which
+    /// case the test is `True`
+    fn translate_loop_test(&self, test: &Expression) -> (String, String) {
+        let outer = self.hoisted.take();
+        let code = self.translate_expression(test);
+        let mut lines = self.hoisted.replace(outer);
+        if lines.is_empty() {
+            return (code, String::new());
+        }
+        lines.push(format!(
+            "if not ({}):\n{}",
+            code,
+            make_indent("break", self.indent)
+        ));
+        (String::from("True"), lines.join("\n"))
     }
 
-    fn translate_function(&self, function: &Function) -> String {
-        let name = function
-            .id
-            .as_ref()
-            .map(|id| id.name.to_string())
-            .unwrap_or_else(|| unimplemented!("anonymous function is not supported"));
+    /// Translate code that runs more than once, the code it hoists is placed right before it
+    /// rather than before the enclosing statement
+    fn translate_with_hoisted(&self, translate: impl FnOnce() -> String) -> String {
+        let outer = self.hoisted.take();
+        let code = translate();
+        let mut lines = self.hoisted.replace(outer);
+        lines.push(code);
+        lines.join("\n")
+    }
 
-        let params = function
-            .params
-            .params
-            .iter()
-            .map(|param| param.name.to_string())
+    /// `do { ... } while (test)` runs the body before checking the test, a `continue` in the
+    /// body checks it too
+    fn translate_do_while_statement(&self, do_while: &DoWhileStatement) -> String {
+        let label = self.loop_label.take();
+        let check = self.translate_with_hoisted(|| {
+            let test = self.translate_expression(&do_while.test);
+            format!("if not ({}):\n{}", test, make_indent("break", self.indent))
+        });
+        let body = self.translate_loop_body(&do_while.body, Some(check.clone()), label);
+        let body = [body, check]
+            .into_iter()
+            .filter(|code| !code.is_empty())
             .collect::<Vec<_>>()
-            .join(", ");
+            .join("\n");
+        format!("while True:\n{}", make_indent(&body, self.indent))
+    }
 
-        let body = function
-            .body
-            .as_ref()
-            .map(|body| self.translate_statements(&body.statements))
-            .unwrap_or_default()
-            .with_placeholder("pass");
+    /// Translate the body of a loop, a `continue` in it runs `update` first
+    fn translate_loop_body(
+        &self,
+        body: &Statement,
+        update: Option<String>,
+        label: Option<String>,
+    ) -> String {
+        self.enter_breakable(BreakableKind::Loop { update }, label);
+        let body = self.translate_statement(body);
+        self.leave_breakable();
+        body
+    }
 
-        format!(
-            "def {}({}):\n{}",
-            name,
-            params,
-            make_indent(&body, self.indent)
-        )
+    fn enter_breakable(&self, kind: BreakableKind, label: Option<String>) {
+        self.breakables.borrow_mut().push(Breakable {
+            kind,
+            label,
+            flags: vec![],
+            exits: vec![],
+        });
     }
 
-    fn translate_return_statement(&self, ret_stmt: &ReturnStatement) -> String {
-        if let Some(argument) = &ret_stmt.argument {
-            format!("return {}", self.translate_expression(argument))
-        } else {
-            String::from("return")
-        }
+    /// Leave the innermost construct, the statement that contains it places its flags
+    fn leave_breakable(&self) {
+        let breakable = self.breakables.borrow_mut().pop();
+        self.left_breakable.replace(breakable);
     }
 
-    #[rustfmt::skip]
-    fn translate_expression(&self, expr: &Expression) -> String {
-        match expr {
-            Expression::BooleanLiteral(b) => (if b.value { "True" } else { "False" }).to_string(),
-            Expression::NumericLiteral(num) => num.value.to_string(),
-            Expression::StringLiteral(s) => s.value.to_string(),
-            Expression::Identifier(id) => id.name.to_string(),
-            Expression::UnaryExpression(u) => self.translate_unary_expression(u),
-            Expression::BinaryExpression(bin_expr) => self.translate_binary_expression(bin_expr),
-            Expression::StaticMemberExpression(mem_expr) => self.translate_static_member_expression(mem_expr),
-            Expression::ComputedMemberExpression(mem_expr) => self.translate_computed_member_expression(mem_expr),
-            Expression::ArrayExpression(arr_expr) => self.translate_array_expression(arr_expr),
-            Expression::AssignmentExpression(assign_expr) => self.translate_assignment_expression(assign_expr),
-            Expression::ObjectExpression(obj_expr) => self.translate_object_expression(obj_expr),
-            Expression::CallExpression(call_expr) => self.translate_call_expression(call_expr),
-            Expression::LogicalExpression(logic_expr) => self.translate_logical_expression(logic_expr),
-            Expression::NullLiteral(_) => String::from("None"),
-            Expression::ParenthesizedExpression(e) => self.translate_parenthesized_expression(e),
-            _ => unimplemented!("unsupported expression {:?}", self.source_of(expr)),
+    /// Reset the flags of the jumps to a construct before it, and check the flags of the jumps
+    /// that leave it after it
+    fn translate_flags(&self, breakable: Breakable, code: String) -> String {
+        let mut lines = breakable
+            .flags
+            .iter()
+            .map(|(flag, _)| format!("{} = False", flag))
+            .collect::<Vec<_>>();
+        lines.push(code);
+        for exit in breakable.exits {
+            let target = self.breakables.borrow().len() - 1;
+            let jump = match exit.target == target {
+                true if exit.is_continue => {
+                    format!("{} = False\n{}", exit.flag, self.translate_continue(target))
+                }
+                true => String::from("break"),
+                // keep breaking out of the enclosing loops
+                false => {
+                    self.add_exit(exit.clone());
+                    String::from("break")
+                }
+            };
+            lines.push(format!(
+                "if {}:\n{}",
+                exit.flag,
+                make_indent(&jump, self.indent)
+            ));
         }
+        lines.join("\n")
     }
 
-    fn translate_parenthesized_expression(&self, parent_expr: &ParenthesizedExpression) -> String {
-        format!("({})", self.translate_expression(&parent_expr.expression))
+    fn add_exit(&self, exit: Exit) {
+        let mut breakables = self.breakables.borrow_mut();
+        let exits = &mut breakables.last_mut().unwrap().exits;
+        if !exits.iter().any(|e| e.flag == exit.flag) {
+            exits.push(exit);
+        }
     }
 
-    fn translate_unary_expression(&self, unary_expr: &UnaryExpression) -> String {
-        let operator = self.translate_unary_operator(&unary_expr.operator);
-        let argument = self.translate_expression(&unary_expr.argument);
-        if operator == "not " {
-            return format!("({}{})", operator, argument);
+    /// Continue the loop at `target` in the stack of breakables, the innermost one
+    fn translate_continue(&self, target: usize) -> String {
+        match &self.breakables.borrow()[target].kind {
+            BreakableKind::Loop {
+                update: Some(update),
+            } => format!("{}\ncontinue", update),
+            _ => String::from("continue"),
         }
-        format!("{}{}", operator, argument)
     }
 
-    fn translate_unary_operator(&self, operator: &UnaryOperator) -> String {
-        match operator {
-            UnaryOperator::LogicalNot => "not ".to_string(),
-            UnaryOperator::UnaryPlus => "+".to_string(),
-            UnaryOperator::UnaryNegation => "-".to_string(),
-            UnaryOperator::BitwiseNot => "~".to_string(),
-            _ => unimplemented!(
-                "unsupported unary operator {:?}",
-                serde_json::to_string(operator).unwrap()
-            ),
+    /// Translate `break` or `continue`, a jump out of nested Python loops sets a flag that is
+    /// checked after each of them
+    fn translate_jump(&self, label: Option<&LabelIdentifier>, is_continue: bool) -> String {
+        let label = label.map(|label| mangle(label.name));
+        let (target, innermost) = {
+            let breakables = self.breakables.borrow();
+            let target = breakables.iter().rposition(|b| match (&label, &b.kind) {
+                (Some(label), _) => b.label.as_ref() == Some(label),
+                (None, BreakableKind::Loop { .. }) => true,
+                (None, BreakableKind::Switch) => !is_continue,
+                (None, BreakableKind::Block) => false,
+            });
+            (target, breakables.len().wrapping_sub(1))
+        };
+        let Some(target) = target else {
+            return String::from(if is_continue { "continue" } else { "break" });
+        };
+        if target == innermost {
+            return if is_continue {
+                self.translate_continue(target)
+            } else {
+                String::from("break")
+            };
         }
+
+        let flag = {
+            let mut breakables = self.breakables.borrow_mut();
+            let flags = &mut breakables[target].flags;
+            match flags.iter().find(|(_, c)| *c == is_continue) {
+                Some((flag, _)) => flag.clone(),
+                None => {
+                    let flag = self.generate_name(if is_continue { "_continue" } else { "_break" });
+                    flags.push((flag.clone(), is_continue));
+                    flag
+                }
+            }
+        };
+        self.add_exit(Exit {
+            flag: flag.clone(),
+            target,
+            is_continue,
+        });
+        format!("{} = True\nbreak", flag)
     }
 
-    fn translate_computed_member_expression(&self, mem_expr: &ComputedMemberExpression) -> String {
-        let object = self.translate_expression(&mem_expr.object);
-        let expression = self.translate_expression(&mem_expr.expression);
-        format!("{}[{}]", object, expression)
+    /// A label is kept on a loop, a labeled block that is left by a `break` becomes a
+    /// `while True` loop
+    fn translate_labeled_statement(&self, labeled: &LabeledStatement) -> String {
+        if !uses_label(&labeled.body, labeled.label.name) {
+            return self.translate_statement(&labeled.body);
+        }
+        let label = mangle(labeled.label.name);
+        if matches!(
+            labeled.body,
+            Statement::ForStatement(_)
+                | Statement::ForInStatement(_)
+                | Statement::ForOfStatement(_)
+                | Statement::WhileStatement(_)
+                | Statement::DoWhileStatement(_)
+        ) {
+            self.loop_label.replace(Some(label));
+            return self.translate_statement(&labeled.body);
+        }
+        self.enter_breakable(BreakableKind::Block, Some(label));
+        let mut body = vec![self.translate_statement(&labeled.body)];
+        self.leave_breakable();
+        // the loop runs once, unless the body ends with a jump already
+        if !ends_with_jump(&body[0]) {
+            body.push(String::from("break"));
+        }
+        body.retain(|code| !code.is_empty());
+        let body = body.join("\n");
+        format!("while True:\n{}", make_indent(&body, self.indent))
     }
 
-    fn translate_logical_expression(&self, logic_expr: &LogicalExpression) -> String {
-        let left = self.translate_expression(&logic_expr.left);
-        let right = self.translate_expression(&logic_expr.right);
-        let operator = match logic_expr.operator {
-            LogicalOperator::Or => "or",
-            LogicalOperator::And => "and",
-            _ => unimplemented!(
-                "unsupported logical operator {:?}",
-                self.source_of(logic_expr)
-            ), // Python 没有 ?? 操作符
+    /// A `switch` is a `match` statement if its cases are literals that do not fall through
+    /// and do not mix booleans with numbers, which `case 1:` would not tell apart, an `if` chain
+    /// if no `break` leaves a case early, and a `while True` loop otherwise
+    fn translate_switch_statement(&self, switch: &SwitchStatement) -> String {
+        let mut groups: Vec<SwitchGroup> = vec![];
+        let mut tests = vec![];
+        for case in &switch.cases {
+            tests.push(case.test.as_ref());
+            if !case.consequent.is_empty() {
+                groups.push(SwitchGroup {
+                    tests: std::mem::take(&mut tests),
+                    consequent: &case.consequent,
+                });
+            }
+        }
+        if !tests.is_empty() {
+            groups.push(SwitchGroup {
+                tests,
+                consequent: &[],
+            });
+        }
+
+        if groups.iter().any(|group| breaks_out(group.body())) {
+            return self.translate_switch_loop(switch, &groups);
+        }
+        let is_literal = |test: &&Expression| {
+            matches!(
+                test,
+                Expression::NumericLiteral(_)
+                    | Expression::StringLiteral(_)
+                    | Expression::BooleanLiteral(_)
+                    | Expression::NullLiteral(_)
+            ) || integer_literal(test).is_some()
         };
-        format!("{} {} {}", left, operator, right)
+        let falls_through = groups
+            .iter()
+            .rev()
+            .skip(1)
+            .any(|group| !group.is_terminated());
+        if !falls_through
+            && !mixes_bool_and_number(&groups)
+            && groups
+                .iter()
+                .flat_map(|g| g.tests.iter().flatten())
+                .all(is_literal)
+        {
+            return self.translate_switch_match(switch, &groups);
+        }
+        self.translate_switch_chain(switch, &groups)
     }
 
-    fn translate_call_expression(&self, call_expr: &CallExpression) -> String {
-        let callee = self.translate_expression(&call_expr.callee);
+    fn translate_switch_match(&self, switch: &SwitchStatement, groups: &[SwitchGroup]) -> String {
+        let discriminant = self.translate_expression(&switch.discriminant);
+        // the `default` case can only be the last one, it is tried last anyway
+        let (defaults, cases): (Vec<_>, Vec<_>) = groups.iter().partition(|g| g.is_default());
+        let cases = cases
+            .into_iter()
+            .chain(defaults)
+            .map(|group| {
+                let pattern = if group.is_default() {
+                    String::from("_")
+                } else {
+                    group
+                        .tests
+                        .iter()
+                        .flatten()
+                        .map(|test| self.translate_expression(test))
+                        .collect::<Vec<_>>()
+                        .join(" | ")
+                };
+                let body = self
+                    .translate_statements(group.body())
+                    .with_placeholder("pass");
+                format!("case {}:\n{}", pattern, make_indent(&body, self.indent))
+            })
+            .collect::<Vec<_>>();
+        if cases.is_empty() {
+            return discriminant;
+        }
+        format!(
+            "match {}:\n{}",
+            discriminant,
+            make_indent(&cases.join("\n"), self.indent)
+        )
+    }
 
-        let arguments = call_expr
-            .arguments
+    /// Evaluate the discriminant of a `switch` once, returns the name it is bound to
+    fn translate_switch_discriminant(
+        &self,
+        switch: &SwitchStatement,
+        code: &mut Vec<String>,
+    ) -> String {
+        let discriminant = self.translate_expression(&switch.discriminant);
+        if matches!(switch.discriminant, Expression::Identifier(_)) {
+            return discriminant;
+        }
+        let name = self.generate_name("_switch");
+        code.push(format!("{} = {}", name, discriminant));
+        name
+    }
+
+    /// The condition to enter a group of cases, `None` for the `default` case
+    ///
+    /// A case compares strictly: a boolean is compared with `is`, and `1` and `0`, which are
+    /// equal to `True` and `False` in Python, do not match them if `mixed` tells there are
+    /// boolean cases.
+    fn translate_switch_test(
+        &self,
+        discriminant: &str,
+        group: &SwitchGroup,
+        mixed: bool,
+    ) -> Option<String> {
+        if group.is_default() {
+            return None;
+        }
+        let tests = group
+            .tests
             .iter()
-            .map(|arg| self.translate_expression(arg))
-            .collect::<Vec<_>>()
-            .join(", ");
+            .flatten()
+            .map(|test| {
+                let code = self.translate_expression(test);
+                match (test, integer_literal(test)) {
+                    (Expression::BooleanLiteral(_), _) => format!("{} is {}", discriminant, code),
+                    (_, Some(n @ (0 | 1))) if mixed => format!(
+                        "{} == {} and {} is not {}",
+                        discriminant,
+                        code,
+                        discriminant,
+                        if n == 1 { "True" } else { "False" }
+                    ),
+                    _ => format!("{} == {}", discriminant, code),
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(tests.join(" or "))
+    }
 
-        // 特殊判断：如果 callee 是 StaticMemberExpression，且 property 为 push
-        if let Expression::StaticMemberExpression(mem_expr) = &call_expr.callee {
-            if let Expression::Identifier(id) = &mem_expr.object {
-                if id.name == "console" && mem_expr.property.name == "log" {
-                    return format!("print({})", arguments);
+    /// An `if` chain where a group of cases that falls through repeats the statements of the
+    /// following groups
+    fn translate_switch_chain(&self, switch: &SwitchStatement, groups: &[SwitchGroup]) -> String {
+        let mut code = vec![];
+        let discriminant = self.translate_switch_discriminant(switch, &mut code);
+        let mixed = mixes_bool_and_number(groups);
+        let mut branches = vec![];
+        let mut default = None;
+        for (i, group) in groups.iter().enumerate() {
+            let mut body = vec![];
+            for next in &groups[i..] {
+                body.push(self.translate_statements(next.body()));
+                if next.is_terminated() {
+                    break;
                 }
             }
-            if mem_expr.property.name == "push" {
-                let object = self.translate_expression(&mem_expr.object);
-                return format!("{}.append({})", object, arguments);
+            body.retain(|code| !code.is_empty());
+            let body = body.join("\n").with_placeholder("pass");
+            match self.translate_switch_test(&discriminant, group, mixed) {
+                Some(test) => branches.push((test, body)),
+                None => default = Some(body),
             }
         }
+        let mut chain = branches
+            .into_iter()
+            .enumerate()
+            .map(|(i, (test, body))| {
+                let keyword = if i == 0 { "if" } else { "elif" };
+                format!("{} {}:\n{}", keyword, test, make_indent(&body, self.indent))
+            })
+            .collect::<Vec<_>>();
+        match default {
+            Some(body) if chain.is_empty() => chain.push(body),
+            Some(body) => chain.push(format!("else:\n{}", make_indent(&body, self.indent))),
+            None => {}
+        }
+        code.extend(chain);
+        code.join("\n")
+    }
 
-        if let Expression::Identifier(id) = &call_expr.callee {
-            if id.name == "parseFloat" {
-                return format!("float({})", arguments);
+    /// A `while True` loop that a `break` leaves, the statements of the group of cases that
+    /// matches and of the following ones run in order
+    fn translate_switch_loop(&self, switch: &SwitchStatement, groups: &[SwitchGroup]) -> String {
+        let mut code = vec![];
+        let discriminant = self.translate_switch_discriminant(switch, &mut code);
+        let entry = self.generate_name("_case");
+        let mut chain = vec![];
+        let mixed = mixes_bool_and_number(groups);
+        let mut default = groups.len();
+        for (i, group) in groups.iter().enumerate() {
+            match self.translate_switch_test(&discriminant, group, mixed) {
+                Some(test) => {
+                    let keyword = if chain.is_empty() { "if" } else { "elif" };
+                    chain.push(format!(
+                        "{} {}:\n{}",
+                        keyword,
+                        test,
+                        make_indent(&format!("{} = {}", entry, i), self.indent)
+                    ));
+                }
+                None => default = i,
             }
         }
+        let default = format!("{} = {}", entry, default);
+        if chain.is_empty() {
+            code.push(default);
+        } else {
+            chain.push(format!("else:\n{}", make_indent(&default, self.indent)));
+            code.extend(chain);
+        }
 
-        format!("{}({})", callee, arguments)
-    }
-
-    fn translate_object_expression(&self, obj_expr: &ObjectExpression) -> String {
-        let properties = obj_expr
-            .properties
+        self.enter_breakable(BreakableKind::Switch, None);
+        let mut body = groups
             .iter()
-            .map(|prop| {
-                let var_name = match &prop.key {
-                    PropertyKey::IdentifierName(id) => format!("\"{}\"", id.name),
-                    PropertyKey::StringLiteral(s) => s.value.to_string(),
-                    PropertyKey::NumericLiteral(n) => n.value.to_string(),
-                };
-                let key = var_name;
-                let value = self.translate_expression(&prop.value);
-                format!("{}: {}", key, value)
+            .enumerate()
+            .map(|(i, group)| {
+                let statements = self
+                    .translate_statements(group.consequent)
+                    .with_placeholder("pass");
+                format!(
+                    "if {} <= {}:\n{}",
+                    entry,
+                    i,
+                    make_indent(&statements, self.indent)
+                )
             })
-            .collect::<Vec<_>>()
-            .join(", ");
-        format!("{{{}}}", properties)
+            .collect::<Vec<_>>();
+        body.push(String::from("break"));
+        self.leave_breakable();
+        code.push(format!(
+            "while True:\n{}",
+            make_indent(&body.join("\n"), self.indent)
+        ));
+        code.join("\n")
     }
 
-    fn translate_array_expression(&self, arr_expr: &ArrayExpression) -> String {
-        let elements = arr_expr
-            .elements
-            .iter()
-            .map(|element| match element {
-                ArrayExpressionElement::Elision(_) => "None".to_string(),
-                ArrayExpressionElement::Expression(expr) => self.translate_expression(expr),
-            })
-            .collect::<Vec<_>>()
-            .join(", ");
-        format!("[{}]", elements)
-    }
+    /// A `for` loop is a `for ... in range(...)` loop if it counts an integer up or down to a
+    /// fixed bound, otherwise a `while` loop
+    fn translate_for_statement(&self, for_stmt: &ForStatement) -> String {
+        let label = self.loop_label.take();
+        if let Some(code) = self.translate_for_range(for_stmt, &label) {
+            return code;
+        }
 
-    fn translate_static_member_expression(&self, mem_expr: &StaticMemberExpression) -> String {
-        let object = self.translate_expression(&mem_expr.object);
-        let property = mem_expr.property.name.to_string();
-        if property == "length" {
-            return format!("len({})", object);
+        let mut code = vec![];
+        match &for_stmt.init {
+            Some(ForStatementInit::VariableDeclaration(var)) => {
+                code.push(self.translate_variable_declaration(var))
+            }
+            Some(ForStatementInit::Expression(init)) => {
+                code.push(self.translate_expression_statement(init))
+            }
+            None => {}
         }
-        format!("{}.{}", object, property)
+        let (test, check) = for_stmt
+            .test
+            .as_ref()
+            .map(|test| self.translate_loop_test(test))
+            .unwrap_or_else(|| (String::from("True"), String::new()));
+        // the update runs at the end of every iteration, its hoisted code runs with it
+        let update = for_stmt.update.as_ref().map(|update| {
+            self.translate_with_hoisted(|| self.translate_expression_statement(update))
+        });
+        let mut body = vec![check];
+        body.push(self.translate_loop_body(&for_stmt.body, update.clone(), label));
+        // a body ending with a jump has run the update already
+        if !ends_with_jump(&body[1]) {
+            body.extend(update);
+        }
+        body.retain(|code| !code.is_empty());
+        let body = body.join("\n").with_placeholder("pass");
+        code.push(format!(
+            "while {}:\n{}",
+            test,
+            make_indent(&body, self.indent)
+        ));
+        code.join("\n")
     }
 
-    fn translate_binary_expression(&self, bin_expr: &BinaryExpression) -> String {
-        let lhs = self.translate_expression(&bin_expr.left);
-        let rhs = self.translate_expression(&bin_expr.right);
-        let op = self.translate_binary_operator(bin_expr.operator);
-        format!("{} {} {}", lhs, op, rhs)
+    fn translate_for_in_statement(&self, for_in: &ForInStatement) -> String {
+        let right = self.translate_expression(&for_in.right);
+        // iterating a list or a string would yield its items rather than its indices
+        let keys = if self.string_keys {
+            self.use_runtime(runtime::JS_STRING_KEYS);
+            format!("js_string_keys({})", right)
+        } else {
+            self.use_runtime(runtime::JS_KEYS);
+            format!("js_keys({})", right)
+        };
+        self.translate_for_each(&for_in.left, keys, &for_in.body)
     }
 
-    fn translate_assignment_expression(&self, assign_expr: &AssignmentExpression) -> String {
-        let left = match &assign_expr.left {
-            AssignmentTarget::Identifier(id) => id.name.to_string(),
-            AssignmentTarget::StaticMemberExpression(mem_expr) => {
-                self.translate_static_member_expression(mem_expr)
+    fn translate_for_of_statement(&self, for_of: &ForOfStatement) -> String {
+        let right = self.translate_expression(&for_of.right);
+        self.translate_for_each(&for_of.left, right, &for_of.body)
+    }
+
+    fn translate_for_each(
+        &self,
+        left: &ForStatementLeft,
+        iter: String,
+        body: &Statement,
+    ) -> String {
+        let label = self.loop_label.take();
+        // a pattern that is not a valid target is bound at the start of the body
+        let mut bindings = vec![];
+        let target = match left {
+            ForStatementLeft::VariableDeclaration(var) => match &var.declarations[0].id {
+                BindingPattern::BindingIdentifier(id) => mangle(id.name),
+                pattern => {
+                    let name = self.generate_name("_value");
+                    bindings = self.translate_binding(pattern, name.clone());
+                    name
+                }
+            },
+            ForStatementLeft::Expression(expr) => self.translate_expression(expr),
+        };
+        bindings.push(self.translate_loop_body(body, None, label));
+        bindings.retain(|code| !code.is_empty());
+        let body = bindings.join("\n").with_placeholder("pass");
+        format!(
+            "for {} in {}:\n{}",
+            target,
+            iter,
+            make_indent(&body, self.indent)
+        )
+    }
+
+    fn translate_for_range(
+        &self,
+        for_stmt: &ForStatement,
+        label: &Option<String>,
+    ) -> Option<String> {
+        let Some(ForStatementInit::VariableDeclaration(var)) = &for_stmt.init else {
+            return None;
+        };
+        // a `var` is still visible after the loop, where `range` would leave it one step short
+        let (VariableDeclarationKind::Let, [declarator]) = (&var.kind, var.declarations.as_slice())
+        else {
+            return None;
+        };
+        let (BindingPattern::BindingIdentifier(id), Some(start)) =
+            (&declarator.id, &declarator.init)
+        else {
+            return None;
+        };
+        let name = id.name;
+        let Some(Expression::BinaryExpression(test)) = &for_stmt.test else {
+            return None;
+        };
+        if !matches!(&test.left, Expression::Identifier(id) if id.name == name) {
+            return None;
+        }
+        let step = match for_stmt.update.as_ref()? {
+            Expression::UpdateExpression(update) if matches!(&update.argument, Expression::Identifier(id) if id.name == name) => {
+                match update.operator {
+                    UpdateOperator::Increment => 1,
+                    UpdateOperator::Decrement => -1,
+                }
             }
-            AssignmentTarget::ComputedMemberExpression(mem_expr) => {
-                self.translate_computed_member_expression(mem_expr)
+            Expression::AssignmentExpression(assign) if matches!(&assign.left, AssignmentTarget::Identifier(id) if id.name == name) =>
+            {
+                let step = integer_literal(&assign.right).filter(|step| *step > 0)?;
+                match assign.operator {
+                    AssignmentOperator::Addition => step,
+                    AssignmentOperator::Subtraction => -step,
+                    _ => return None,
+                }
             }
+            _ => return None,
         };
-        let operator = self.translate_assign_operator(&assign_expr.operator);
-        let right = self.translate_expression(&assign_expr.right);
-        format!("{} {} {}", left, operator, right)
+        // the bound is evaluated once by `range`, it must not change in the loop
+        let bound = integer_literal(&test.right).map(|n| n.to_string());
+        let bound = match (&test.right, bound) {
+            (_, Some(bound)) => bound,
+            (Expression::Identifier(id), None)
+                if id.name != name && !is_written(&for_stmt.body, id.name) =>
+            {
+                mangle(id.name)
+            }
+            _ => return None,
+        };
+        let stop = match (test.operator, step > 0) {
+            (BinaryOperator::LessThan, true) | (BinaryOperator::GreaterThan, false) => bound,
+            (BinaryOperator::LessEqualThan, true) => match integer_literal(&test.right) {
+                Some(n) => (n + 1).to_string(),
+                None => format!("{} + 1", bound),
+            },
+            (BinaryOperator::GreaterEqualThan, false) => match integer_literal(&test.right) {
+                Some(n) => (n - 1).to_string(),
+                None => format!("{} - 1", bound),
+            },
+            _ => return None,
+        };
+        if is_written(&for_stmt.body, name)
+            || matches!(start, Expression::NumericLiteral(_)) && integer_literal(start).is_none()
+        {
+            return None;
+        }
+
+        let start = self.translate_expression(start);
+        let args = match step {
+            1 if start == "0" => stop,
+            1 => format!("{}, {}", start, stop),
+            _ => format!("{}, {}, {}", start, stop, step),
+        };
+        let body = self
+            .translate_loop_body(&for_stmt.body, None, label.clone())
+            .with_placeholder("pass");
+        Some(format!(
+            "for {} in range({}):\n{}",
+            mangle(name),
+            args,
+            make_indent(&body, self.indent)
+        ))
     }
 
-    fn translate_assign_operator(&self, operator: &AssignmentOperator) -> String {
-        use AssignmentOperator::*;
-        match operator {
-            ShiftRightZeroFill | LogicalAnd | LogicalOr | LogicalNullish => unimplemented!(
-                "unsupported assignment operator {:?}",
-                serde_json::to_string(&operator).unwrap()
+    /// Every declarator is an assignment of its own, a declaration without an initializer assigns
+    /// `None`
+    fn translate_variable_declaration(&self, var: &VariableDeclaration) -> String {
+        var.declarations
+            .iter()
+            .map(|declarator| self.translate_variable_declarator(declarator))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn translate_variable_declarator(&self, declarator: &VariableDeclarator) -> String {
+        let BindingPattern::BindingIdentifier(id) = &declarator.id else {
+            let value = declarator
+                .init
+                .as_ref()
+                .map(|init| self.translate_expression(init))
+                .unwrap_or_else(|| String::from("None"));
+            return self.translate_binding(&declarator.id, value).join("\n");
+        };
+        let name = mangle(id.name);
+        // `var f = function () { ... }` is a function definition
+        if let Some(Expression::FunctionExpression(function)) = &declarator.init {
+            if function.id.as_ref().is_none_or(|f| f.name == id.name) {
+                return self.translate_function_def(&name, function);
+            }
+        }
+        // `const A = class { ... }` is a class definition
+        if let Some(Expression::ClassExpression(class)) = &declarator.init {
+            if class.id.as_ref().is_none_or(|c| c.name == id.name) {
+                return self.translate_class(&name, class);
+            }
+        }
+        // `const f = () => { ... }` is a function definition
+        if let Some(Expression::ArrowFunctionExpression(arrow)) = &declarator.init {
+            return match self.translate_arrow_function(arrow) {
+                ArrowFunction::Lambda(lambda) => format!("{} = {}", name, lambda),
+                ArrowFunction::Def(body) => {
+                    self.translate_def(&name, self.translate_params(&arrow.params), body)
+                }
+            };
+        }
+        let value = match &declarator.init {
+            Some(init) => self.translate_expression(init),
+            None => String::from("None"),
+        };
+        format!("{} = {}", name, value)
+    }
+
+    /// Bind the names of a pattern to the parts of `value`: an array pattern is a tuple
+    /// unpacking, an object pattern reads every key on its own line
+    fn translate_binding(&self, pattern: &BindingPattern, value: String) -> Vec<String> {
+        match pattern {
+            BindingPattern::BindingIdentifier(id) => {
+                vec![format!("{} = {}", mangle(id.name), value)]
+            }
+            BindingPattern::AssignmentPattern(assign) => {
+                let (name, mut code) = match &assign.left {
+                    BindingPattern::BindingIdentifier(id) => (mangle(id.name), vec![]),
+                    _ => (self.generate_name("_value"), vec![]),
+                };
+                code.push(format!("{} = {}", name, value));
+                code.push(self.translate_default(&name, &assign.right));
+                if !matches!(assign.left, BindingPattern::BindingIdentifier(_)) {
+                    code.extend(self.translate_binding(&assign.left, name));
+                }
+                code
+            }
+            BindingPattern::ArrayPattern(array) => self.translate_array_binding(array, value),
+            BindingPattern::ObjectPattern(object) => {
+                let mut code = vec![];
+                // every key reads the source again
+                let reads = object.properties.len() + usize::from(object.rest.is_some());
+                let source = if reads <= 1 || is_simple_name(&value) {
+                    value
+                } else {
+                    let name = self.generate_name("_value");
+                    code.push(format!("{} = {}", name, value));
+                    name
+                };
+                // a missing key is `None`, like it is `undefined` in JavaScript
+                for property in &object.properties {
+                    self.use_runtime(runtime::JS_GET);
+                    let key = self.translate_property_key(&property.key);
+                    match &property.value {
+                        BindingPattern::AssignmentPattern(assign) => {
+                            let default = self.translate_expression(&assign.right);
+                            let value = format!("js_get({}, {}, {})", source, key, default);
+                            code.extend(self.translate_binding(&assign.left, value));
+                        }
+                        pattern => {
+                            let value = format!("js_get({}, {})", source, key);
+                            code.extend(self.translate_binding(pattern, value));
+                        }
+                    }
+                }
+                if let Some(rest) = &object.rest {
+                    let keys = object
+                        .properties
+                        .iter()
+                        .map(|property| self.translate_property_key(&property.key))
+                        .collect::<Vec<_>>();
+                    let value = match keys.as_slice() {
+                        [] => format!("dict({})", source),
+                        [key] => format!("{{k: v for k, v in {}.items() if k != {}}}", source, key),
+                        keys => format!(
+                            "{{k: v for k, v in {}.items() if k not in ({})}}",
+                            source,
+                            keys.join(", ")
+                        ),
+                    };
+                    code.extend(self.translate_binding(&rest.argument, value));
+                }
+                code
+            }
+        }
+    }
+
+    /// Unpack the value of an array pattern padded with `None` and cut to the length of the
+    /// pattern, since JavaScript reads missing elements as `undefined` and ignores extra ones
+    fn translate_array_binding(&self, array: &ArrayPattern, value: String) -> Vec<String> {
+        let mut bindings = vec![];
+        let targets = array
+            .elements
+            .iter()
+            .map(|element| match element {
+                None => String::from("_"),
+                Some(element) => self.translate_element_target(element, &mut bindings),
+            })
+            .collect::<Vec<_>>();
+        let len = targets.len();
+        let mut code = vec![];
+        let source = match &array.rest {
+            // the rest is a slice of the same list
+            Some(_) if len > 0 => {
+                let name = self.generate_name("_value");
+                code.push(format!("{} = list({})", name, value));
+                name
+            }
+            _ => format!("list({})", value),
+        };
+        match targets.as_slice() {
+            [] if array.rest.is_none() => code.push(format!("_ = {}", source)),
+            [] => {}
+            [target] => code.push(format!("{}, = ({} + [None])[:1]", target, source)),
+            targets => code.push(format!(
+                "{} = ({} + [None] * {})[:{}]",
+                targets.join(", "),
+                source,
+                len,
+                len
+            )),
+        }
+        if let Some(rest) = &array.rest {
+            let target = self.translate_element_target(&rest.argument, &mut bindings);
+            if len == 0 {
+                code.push(format!("{} = {}", target, source));
+            } else {
+                code.push(format!("{} = {}[{}:]", target, source, len));
+            }
+        }
+        code.extend(bindings);
+        code
+    }
+
+    /// The target of an element of an array pattern, an element that is not a name is unpacked
+    /// to a temporary and bound by the lines added to `bindings`
+    fn translate_element_target(
+        &self,
+        element: &BindingPattern,
+        bindings: &mut Vec<String>,
+    ) -> String {
+        match element {
+            BindingPattern::BindingIdentifier(id) => mangle(id.name),
+            BindingPattern::AssignmentPattern(assign)
+                if matches!(assign.left, BindingPattern::BindingIdentifier(_)) =>
+            {
+                let name = self.translate_element_target(&assign.left, bindings);
+                bindings.push(self.translate_default(&name, &assign.right));
+                name
+            }
+            _ => {
+                let name = self.generate_name("_value");
+                bindings.extend(self.translate_binding(element, name.clone()));
+                name
+            }
+        }
+    }
+
+    /// A default value replaces a missing value, which is `None` after the unpacking
+    fn translate_default(&self, name: &str, default: &Expression) -> String {
+        format!(
+            "{} = {} if {} is None else {}",
+            name,
+            self.translate_expression(default),
+            name,
+            name
+        )
+    }
+
+    fn translate_function(&self, function: &Function) -> String {
+        let name = function
+            .id
+            .as_ref()
+            .map(|id| mangle(id.name))
+            .unwrap_or_else(|| self.unsupported(function, "anonymous function"));
+        self.translate_function_def(&name, function)
+    }
+
+    /// Lift a function expression to a `def` placed before the enclosing statement, under a
+    /// generated name since the name of a function expression is only visible in its body
+    fn translate_function_expression(&self, function: &Function) -> String {
+        let name = self.generate_name("_function");
+        self.hoist(self.translate_function_def(&name, function));
+        name
+    }
+
+    fn translate_function_def(&self, name: &str, function: &Function) -> String {
+        let statements = function
+            .body
+            .as_ref()
+            .map(|body| &body.statements[..])
+            .unwrap_or_default();
+        // a function expression defined under another name refers to itself by its own name
+        let alias = function
+            .id
+            .as_ref()
+            .filter(|id| mangle(id.name) != name && is_read(statements, id.name))
+            .map(|id| format!("{} = {}", mangle(id.name), name));
+        let body = self.translate_function_body(&function.params, statements, || {
+            let body = self.with_this(ThisBinding::Global, || {
+                self.translate_statements(statements)
+            });
+            match alias {
+                Some(alias) if body.is_empty() => alias,
+                Some(alias) => format!("{}\n{}", alias, body),
+                None => body,
+            }
+        });
+        self.translate_def(name, self.translate_params(&function.params), body)
+    }
+
+    /// Translate the body of a function, preceded by the `nonlocal` and `global` declarations of
+    /// the variables it assigns that belong to an enclosing scope
+    fn translate_function_body(
+        &self,
+        params: &FormalParameters,
+        statements: &[Statement],
+        translate: impl FnOnce() -> String,
+    ) -> String {
+        let (declarations, body) = self.with_function_scope(params, statements, translate);
+        let mut lines = declarations;
+        lines.push(body);
+        lines.retain(|line| !line.is_empty());
+        lines.join("\n")
+    }
+
+    /// Translate in the scope of a function, also returns the declarations of the variables the
+    /// function assigns that belong to an enclosing scope: a variable of an enclosing function is
+    /// `nonlocal`, any other one is `global`
+    fn with_function_scope<T>(
+        &self,
+        params: &FormalParameters,
+        statements: &[Statement],
+        translate: impl FnOnce() -> T,
+    ) -> (Vec<String>, T) {
+        let scope = FunctionScope::new(params, statements);
+        let (mut nonlocals, mut globals) = (vec![], vec![]);
+        for name in scope.captured() {
+            if self.scopes.borrow().iter().any(|s| s.contains(name)) {
+                nonlocals.push(mangle(name));
+            } else {
+                globals.push(mangle(name));
+            }
+        }
+        let mut declarations = vec![];
+        if !globals.is_empty() {
+            declarations.push(format!("global {}", globals.join(", ")));
+        }
+        if !nonlocals.is_empty() {
+            declarations.push(format!("nonlocal {}", nonlocals.join(", ")));
+        }
+
+        let declared = scope.declared.iter().map(|name| name.to_string()).collect();
+        self.scopes.borrow_mut().push(declared);
+        let translated = translate();
+        self.scopes.borrow_mut().pop();
+        (declarations, translated)
+    }
+
+    /// Translate a function body where `this` refers to `binding`, an arrow function keeps the
+    /// binding of the enclosing function
+    fn with_this(&self, binding: ThisBinding, translate: impl FnOnce() -> String) -> String {
+        let outer = self.this_binding.replace(binding);
+        let code = translate();
+        self.this_binding.replace(outer);
+        code
+    }
+
+    /// `self` in methods, the class in static methods, and an object standing for the global
+    /// object elsewhere
+    fn translate_this(&self) -> String {
+        match &*self.this_binding.borrow() {
+            ThisBinding::Instance { .. } => String::from("self"),
+            ThisBinding::Class(name) => name.clone(),
+            ThisBinding::Global => {
+                self.import("types");
+                self.use_runtime(runtime::JS_THIS);
+                String::from("js_this")
+            }
+        }
+    }
+
+    /// `new.target` is the class of the instance being constructed, and `None` outside of
+    /// constructors
+    fn translate_meta_property(&self, meta: &MetaProperty) -> String {
+        if meta.property.name != "target" {
+            self.unsupported(meta, "meta property");
+        }
+        match &*self.this_binding.borrow() {
+            ThisBinding::Instance { constructor: true } => String::from("type(self)"),
+            _ => String::from("None"),
+        }
+    }
+
+    /// `new C(...)` is a call to the class, the common built-in classes are mapped to their
+    /// Python counterparts
+    fn translate_new_expression(&self, new: &NewExpression) -> String {
+        let arguments = new
+            .arguments
+            .iter()
+            .map(|arg| self.translate_expression(arg))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let callee = match &new.callee {
+            Expression::Identifier(id) if id.name == "Map" => String::from("dict"),
+            Expression::Identifier(id) if id.name == "Set" => String::from("set"),
+            Expression::Identifier(id) if id.name == "Error" => {
+                self.use_runtime(runtime::JS_ERROR);
+                String::from("JSError")
+            }
+            callee => self.translate_expression(callee),
+        };
+        format!("{}({})", callee, arguments)
+    }
+
+    fn translate_params(&self, params: &FormalParameters) -> Vec<String> {
+        params
+            .params
+            .iter()
+            .map(|param| mangle(param.name))
+            .collect()
+    }
+
+    fn translate_def(&self, name: &str, params: Vec<String>, body: String) -> String {
+        format!(
+            "def {}({}):\n{}",
+            name,
+            params.join(", "),
+            make_indent(&body.with_placeholder("pass"), self.indent)
+        )
+    }
+
+    fn translate_arrow_function_expression(&self, arrow: &ArrowFunctionExpression) -> String {
+        match self.translate_arrow_function(arrow) {
+            ArrowFunction::Lambda(lambda) => lambda,
+            // a `def` is a statement, it is placed before the enclosing one
+            ArrowFunction::Def(body) => {
+                let name = self.generate_name("_arrow");
+                self.hoist(self.translate_def(&name, self.translate_params(&arrow.params), body));
+                name
+            }
+        }
+    }
+
+    /// An arrow function is a `lambda` if its body is an expression that needs no statement
+    fn translate_arrow_function(&self, arrow: &ArrowFunctionExpression) -> ArrowFunction {
+        let (declarations, (mut body, expression)) =
+            self.with_function_scope(&arrow.params, &arrow.body.statements, || {
+                self.translate_arrow_body(arrow)
+            });
+        let expression = match expression {
+            Some(expression) if declarations.is_empty() && body.is_empty() => expression,
+            expression => {
+                body.extend(expression.map(|expression| format!("return {}", expression)));
+                let mut lines = declarations;
+                lines.extend(body);
+                lines.retain(|line| !line.is_empty());
+                return ArrowFunction::Def(lines.join("\n"));
+            }
+        };
+
+        let params = self.translate_params(&arrow.params);
+        if params.is_empty() {
+            ArrowFunction::Lambda(format!("lambda: {}", expression))
+        } else {
+            ArrowFunction::Lambda(format!("lambda {}: {}", params.join(", "), expression))
+        }
+    }
+
+    /// The statements of the body of an arrow function and the value it returns, if its body is
+    /// an expression
+    fn translate_arrow_body(
+        &self,
+        arrow: &ArrowFunctionExpression,
+    ) -> (Vec<String>, Option<String>) {
+        let stmt = match &arrow.body.statements[..] {
+            [Statement::ExpressionStatement(stmt)] if arrow.expression => stmt,
+            statements => return (vec![self.translate_statements(statements)], None),
+        };
+        // an assignment is a statement in Python, the arrow function returns the assigned value
+        if let Expression::AssignmentExpression(assign) = &stmt.expression {
+            let outer = self.hoisted.take();
+            let code = self.translate_expression_statement(&stmt.expression);
+            let mut body = self.hoisted.replace(outer);
+            body.push(code);
+            return (body, Some(self.translate_assignment_target(&assign.left)));
+        }
+
+        // the code hoisted from the body must stay in the body
+        let outer = self.hoisted.take();
+        let expression = self.translate_expression(&stmt.expression);
+        (self.hoisted.replace(outer), Some(expression))
+    }
+
+    fn translate_class_declaration(&self, class: &Class) -> String {
+        let name = class
+            .id
+            .as_ref()
+            .map(|id| mangle(id.name))
+            .unwrap_or_else(|| self.unsupported(class, "anonymous class"));
+        self.translate_class(&name, class)
+    }
+
+    /// Lift a class expression to a `class` placed before the enclosing statement
+    fn translate_class_expression(&self, class: &Class) -> String {
+        let name = match &class.id {
+            Some(id) => mangle(id.name),
+            None => self.generate_name("_class"),
+        };
+        self.hoist(self.translate_class(&name, class));
+        name
+    }
+
+    fn translate_class(&self, name: &str, class: &Class) -> String {
+        let base = class.super_class.as_ref().map(|base| match base {
+            Expression::Identifier(id) if id.name == "Error" => {
+                self.use_runtime(runtime::JS_ERROR);
+                String::from("JSError")
+            }
+            base => self.translate_expression(base),
+        });
+        if class
+            .super_class
+            .as_ref()
+            .is_some_and(|base| self.is_error_class(base))
+        {
+            self.error_classes.borrow_mut().insert(name.to_string());
+        }
+
+        let mut members = vec![];
+        // instance fields are initialized by `__init__`
+        let mut fields = vec![];
+        // the class does not exist yet in its body, so a static field using `this` and the ones
+        // after it are assigned after the class
+        let mut static_fields = vec![];
+        for element in &class.body {
+            if let ClassElement::PropertyDefinition(field) = element {
+                let key = self.translate_member_name(&field.key);
+                let binding = if field.is_static {
+                    ThisBinding::Class(name.to_string())
+                } else {
+                    ThisBinding::Instance { constructor: true }
+                };
+                let value = self.with_this(binding, || {
+                    field
+                        .value
+                        .as_ref()
+                        .map(|value| self.translate_expression(value))
+                        .unwrap_or_else(|| String::from("None"))
+                });
+                if field.is_static {
+                    if static_fields.is_empty() && !field.value.as_ref().is_some_and(uses_this) {
+                        members.push(format!("{} = {}", key, value));
+                    } else {
+                        static_fields.push(format!("{}.{} = {}", name, key, value));
+                    }
+                } else {
+                    fields.push(format!("self.{} = {}", key, value));
+                }
+            }
+        }
+
+        let methods = class.body.iter().filter_map(|element| match element {
+            ClassElement::MethodDefinition(method) => Some(method),
+            ClassElement::PropertyDefinition(_) => None,
+        });
+        let constructor = methods
+            .clone()
+            .find(|method| method.kind == MethodDefinitionKind::Constructor);
+        match constructor {
+            Some(constructor) => {
+                members.push(
+                    self.with_this(ThisBinding::Instance { constructor: true }, || {
+                        self.translate_constructor(constructor, &fields)
+                    }),
+                );
+            }
+            None if !fields.is_empty() => {
+                let (params, mut body) = match base {
+                    // the implicit constructor passes its arguments to the base class
+                    Some(_) => (
+                        vec!["self", "*args", "**kwargs"],
+                        vec![String::from("super().__init__(*args, **kwargs)")],
+                    ),
+                    None => (vec!["self"], vec![]),
+                };
+                body.extend(fields);
+                let params = params.into_iter().map(String::from).collect();
+                members.push(self.translate_def("__init__", params, body.join("\n")));
+            }
+            None => {}
+        }
+
+        // a setter is declared after the getter of the same property
+        let getters = methods
+            .clone()
+            .filter(|method| method.kind == MethodDefinitionKind::Get && !method.is_static)
+            .map(|method| self.translate_member_name(&method.key))
+            .collect::<Vec<_>>();
+        let mut deferred_setters: Vec<(String, String)> = vec![];
+        let mut defined_getters = vec![];
+        for method in methods {
+            let key = self.translate_member_name(&method.key);
+            // assigning to a class attribute replaces it, whatever descriptor it holds
+            if method.is_static && method.kind == MethodDefinitionKind::Set {
+                self.warn(method.as_ref(), "static setters are not supported");
+                members.push(self.todo_comment(method.as_ref(), "static setter"));
+                continue;
+            }
+            let mut params = self.translate_params(&method.value.params);
+            if !method.is_static {
+                params.insert(0, String::from("self"));
+            }
+            let binding = if method.is_static {
+                ThisBinding::Class(name.to_string())
+            } else {
+                ThisBinding::Instance { constructor: false }
+            };
+            let statements = method
+                .value
+                .body
+                .as_ref()
+                .map(|body| &body.statements[..])
+                .unwrap_or_default();
+            let body = self.translate_function_body(&method.value.params, statements, || {
+                self.with_this(binding, || self.translate_statements(statements))
+            });
+            let def = self.translate_def(&key, params, body);
+            match method.kind {
+                MethodDefinitionKind::Constructor => {}
+                MethodDefinitionKind::Method if method.is_static => {
+                    members.push(format!("@staticmethod\n{}", def));
+                }
+                MethodDefinitionKind::Method => members.push(def),
+                MethodDefinitionKind::Get if method.is_static => {
+                    self.use_runtime(runtime::JS_CLASSPROPERTY);
+                    members.push(format!("@js_classproperty\n{}", def));
+                }
+                MethodDefinitionKind::Get => {
+                    members.push(format!("@property\n{}", def));
+                    defined_getters.push(key.clone());
+                    deferred_setters.retain(|(name, setter)| {
+                        if *name == key {
+                            members.push(setter.clone());
+                        }
+                        *name != key
+                    });
+                }
+                MethodDefinitionKind::Set => {
+                    let setter = format!("@{}.setter\n{}", key, def);
+                    if defined_getters.contains(&key) {
+                        members.push(setter);
+                    } else if getters.contains(&key) {
+                        deferred_setters.push((key, setter));
+                    } else {
+                        // a write-only property
+                        members.push(format!("{} = property()\n{}", key, setter));
+                    }
+                }
+            }
+        }
+
+        let header = match base {
+            Some(base) => format!("class {}({}):", name, base),
+            None => format!("class {}:", name),
+        };
+        let body = members.join("\n").with_placeholder("pass");
+        let mut lines = vec![format!("{}\n{}", header, make_indent(&body, self.indent))];
+        lines.extend(static_fields);
+        lines.join("\n")
+    }
+
+    /// Translate a constructor to `__init__`, the instance fields are initialized after the call
+    /// to the base class constructor if there is one
+    fn translate_constructor(&self, constructor: &MethodDefinition, fields: &[String]) -> String {
+        let mut params = self.translate_params(&constructor.value.params);
+        params.insert(0, String::from("self"));
+        let statements = constructor
+            .value
+            .body
+            .as_ref()
+            .map(|body| &body.statements[..])
+            .unwrap_or_default();
+        let super_call = statements.iter().position(|stmt| {
+            matches!(
+                stmt,
+                Statement::ExpressionStatement(e) if matches!(
+                    &e.expression,
+                    Expression::CallExpression(call) if matches!(call.callee, Expression::Super(_))
+                )
+            )
+        });
+        let (before, after) = match super_call {
+            Some(i) => statements.split_at(i + 1),
+            None => statements.split_at(0),
+        };
+        let body = self.translate_function_body(&constructor.value.params, statements, || {
+            let mut body = vec![self.translate_statements(before)];
+            body.extend(fields.iter().cloned());
+            body.push(self.translate_statements(after));
+            body.retain(|code| !code.is_empty());
+            body.join("\n")
+        });
+        self.translate_def("__init__", params, body)
+    }
+
+    /// The Python name of a class member
+    fn translate_member_name(&self, key: &PropertyKey) -> String {
+        match key {
+            PropertyKey::IdentifierName(id) => mangle(id.name),
+            PropertyKey::StringLiteral(s) => mangle(&s.value[1..s.value.len() - 1]),
+            PropertyKey::NumericLiteral(n) => self.unsupported(n, "numeric member name"),
+        }
+    }
+
+    /// Raise the thrown value wrapped in a `JSError`, an exception caught by an enclosing
+    /// `catch` clause and a new `Error`, or a new instance of a class extending it, are raised
+    /// as is
+    fn translate_throw_statement(&self, throw: &ThrowStatement) -> String {
+        let argument = self.translate_expression(&throw.argument);
+        match &throw.argument {
+            Expression::Identifier(id) if self.catch_params.borrow().contains(&mangle(id.name)) => {
+                return format!("raise {}", argument);
+            }
+            Expression::NewExpression(new) if self.is_error_class(&new.callee) => {
+                return format!("raise {}", argument);
+            }
+            _ => {}
+        }
+        self.use_runtime(runtime::JS_ERROR);
+        format!("raise JSError({})", argument)
+    }
+
+    /// Whether `expr` is `Error` or a class extending it, which is a `JSError`
+    fn is_error_class(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Identifier(id) => {
+                id.name == "Error" || self.error_classes.borrow().contains(&mangle(id.name))
+            }
+            _ => false,
+        }
+    }
+
+    fn translate_try_statement(&self, try_stmt: &TryStatement) -> String {
+        let mut code = format!(
+            "try:\n{}",
+            make_indent(
+                &self
+                    .translate_block_statement(&try_stmt.block)
+                    .with_placeholder("pass"),
+                self.indent
+            )
+        );
+        if let Some(handler) = &try_stmt.handler {
+            let param = handler.param.as_ref().map(|param| mangle(param.name));
+            let header = match &param {
+                Some(param) => format!("except Exception as {}:", param),
+                None => String::from("except Exception:"),
+            };
+            self.catch_params.borrow_mut().extend(param.clone());
+            let body = self.translate_block_statement(&handler.body);
+            if param.is_some() {
+                self.catch_params.borrow_mut().pop();
+            }
+            code += &format!(
+                "\n{}\n{}",
+                header,
+                make_indent(&body.with_placeholder("pass"), self.indent)
+            );
+        }
+        if let Some(finalizer) = &try_stmt.finalizer {
+            code += &format!(
+                "\nfinally:\n{}",
+                make_indent(
+                    &self
+                        .translate_block_statement(finalizer)
+                        .with_placeholder("pass"),
+                    self.indent
+                )
+            );
+        }
+        code
+    }
+
+    fn translate_return_statement(&self, ret_stmt: &ReturnStatement) -> String {
+        if let Some(argument) = &ret_stmt.argument {
+            format!("return {}", self.translate_expression(argument))
+        } else {
+            String::from("return")
+        }
+    }
+
+    #[rustfmt::skip]
+    fn translate_expression(&self, expr: &Expression) -> String {
+        let span = expr.span();
+        if let Some((_, name)) = self.substitutions.borrow().iter().find(|(s, _)| *s == span) {
+            return name.clone();
+        }
+        match expr {
+            Expression::BooleanLiteral(b) => (if b.value { "True" } else { "False" }).to_string(),
+            Expression::NumericLiteral(num) => self.translate_numeric_literal(num),
+            Expression::StringLiteral(s) => s.value.to_string(),
+            Expression::TemplateLiteral(t) => self.translate_template_literal(t),
+            Expression::RegExpLiteral(re) => self.translate_regexp_literal(re),
+            Expression::Identifier(id) => mangle(id.name),
+            Expression::UnaryExpression(u) => self.translate_unary_expression(u),
+            Expression::BinaryExpression(bin_expr) => self.translate_binary_expression(bin_expr),
+            Expression::StaticMemberExpression(mem_expr) => self.translate_static_member_expression(mem_expr),
+            Expression::ComputedMemberExpression(mem_expr) => self.translate_computed_member_expression(mem_expr),
+            Expression::ArrayExpression(arr_expr) => self.translate_array_expression(arr_expr),
+            Expression::AssignmentExpression(assign_expr) => self.translate_assignment_expression(assign_expr),
+            Expression::UpdateExpression(update) => self.translate_update_expression(update),
+            Expression::ObjectExpression(obj_expr) => self.translate_object_expression(obj_expr),
+            Expression::CallExpression(call_expr) => self.translate_call_expression(call_expr),
+            Expression::ChainExpression(chain) => self.translate_chain_expression(chain),
+            Expression::ArrowFunctionExpression(arrow) => self.translate_arrow_function_expression(arrow),
+            Expression::FunctionExpression(function) => self.translate_function_expression(function),
+            Expression::ClassExpression(class) => self.translate_class_expression(class),
+            Expression::ThisExpression(_) => self.translate_this(),
+            Expression::NewExpression(new) => self.translate_new_expression(new),
+            Expression::MetaProperty(meta) => self.translate_meta_property(meta),
+            // `super.method`, a call to `super` itself is translated with the call
+            Expression::Super(_) => String::from("super()"),
+            Expression::LogicalExpression(logic_expr) => self.translate_logical_expression(logic_expr),
+            Expression::ConditionalExpression(cond) => self.translate_conditional_expression(cond),
+            Expression::NullLiteral(_) => String::from("None"),
+            Expression::ParenthesizedExpression(e) => self.translate_parenthesized_expression(e),
+            Expression::ErrorExpression(e) => self.translate_error_expression(e),
+            _ => self.unsupported(expr, "expression"),
+        }
+    }
+
+    /// Translate an expression whose value is not used
+    fn translate_expression_statement(&self, expr: &Expression) -> String {
+        match expr {
+            Expression::UpdateExpression(update) => {
+                let argument = self.translate_expression(&update.argument);
+                match update.operator {
+                    UpdateOperator::Increment => format!("{} += 1", argument),
+                    UpdateOperator::Decrement => format!("{} -= 1", argument),
+                }
+            }
+            Expression::AssignmentExpression(assign)
+                if matches!(
+                    assign.operator,
+                    AssignmentOperator::LogicalAnd
+                        | AssignmentOperator::LogicalOr
+                        | AssignmentOperator::LogicalNullish
+                ) =>
+            {
+                self.translate_logical_assignment_statement(assign)
+            }
+            // `f?.()` calls `f` under an `if`
+            Expression::ChainExpression(chain) => {
+                let (tests, code) = self.translate_chain(chain);
+                let tests = tests
+                    .iter()
+                    .map(|test| format!("{} is not None", test))
+                    .collect::<Vec<_>>();
+                format!(
+                    "if {}:\n{}",
+                    tests.join(" and "),
+                    make_indent(&code, self.indent)
+                )
+            }
+            // `a ? b = 1 : c = 2` assigns in an `if` statement
+            Expression::ConditionalExpression(cond)
+                if as_assignment(&cond.consequent).is_some()
+                    || as_assignment(&cond.alternate).is_some() =>
+            {
+                let test = self.translate_expression(&cond.test);
+                let consequent = self.translate_expression_statement(&cond.consequent);
+                let alternate = self.translate_expression_statement(&cond.alternate);
+                format!(
+                    "if {}:\n{}\nelse:\n{}",
+                    test,
+                    make_indent(&consequent, self.indent),
+                    make_indent(&alternate, self.indent)
+                )
+            }
+            Expression::ParenthesizedExpression(e) if as_assignment(&e.expression).is_some() => {
+                self.translate_expression_statement(&e.expression)
+            }
+            Expression::SequenceExpression(seq) => seq
+                .expressions
+                .iter()
+                .map(|expr| self.translate_expression_statement(expr))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => self.translate_expression(expr),
+        }
+    }
+
+    fn translate_error_expression(&self, error: &ErrorExpression) -> String {
+        self.hoist(self.todo_comment(error, "syntax error"));
+        String::from("None")
+    }
+
+    fn translate_numeric_literal(&self, num: &NumericLiteral) -> String {
+        // Python integers are arbitrary-precision, so a BigInt is a plain integer
+        let raw = num.raw.strip_suffix('n').unwrap_or(num.raw);
+        if !num.value.is_finite() {
+            return String::from("float(\"inf\")");
+        }
+        // hex, octal and binary literals are written the same way in Python
+        if raw.len() > 1 && raw.starts_with('0') && raw.as_bytes()[1].is_ascii_alphabetic() {
+            return raw.to_string();
+        }
+        if raw.contains(['.', 'e', 'E']) && num.value.fract() == 0.0 {
+            return format!("{:.0}", num.value);
+        }
+        raw.to_string()
+    }
+
+    fn translate_regexp_literal(&self, regexp: &RegExpLiteral) -> String {
+        let (pattern, flags) = self.translate_regexp(regexp);
+        if flags.is_empty() {
+            format!("re.compile({})", pattern)
+        } else {
+            format!("re.compile({}, {})", pattern, flags)
+        }
+    }
+
+    /// Translate a regular expression to a Python raw string and `re` flags
+    fn translate_regexp(&self, regexp: &RegExpLiteral) -> (String, String) {
+        self.import("re");
+        let mut flags = vec![];
+        for flag in regexp.flags.chars() {
+            match flag {
+                'i' => flags.push("re.I"),
+                'm' => flags.push("re.M"),
+                's' => flags.push("re.S"),
+                // `g` only affects the methods using the pattern, Python patterns are unicode
+                'g' | 'u' => {}
+                'y' => self.warn(
+                    regexp,
+                    "the sticky flag `y` is not supported by Python `re`",
+                ),
+                f => self.warn(
+                    regexp,
+                    &format!("unsupported regular expression flag `{}`", f),
+                ),
+            }
+        }
+
+        let mut pattern = String::with_capacity(regexp.pattern.len());
+        let mut chars = regexp.pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    // named backreference
+                    Some('k') if chars.peek() == Some(&'<') => {
+                        chars.next();
+                        let name = chars.by_ref().take_while(|&c| c != '>').collect::<String>();
+                        pattern.push_str(&format!("(?P={})", name));
+                    }
+                    Some('u') if chars.peek() == Some(&'{') => {
+                        chars.next();
+                        let hex = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+                        let code = u32::from_str_radix(&hex, 16).unwrap_or_default();
+                        pattern.push_str(&format!("\\U{:08x}", code));
+                    }
+                    Some('/') => pattern.push('/'),
+                    Some(e) => {
+                        if e == 'c' {
+                            self.warn(
+                                regexp,
+                                "control escapes `\\c` are not supported by Python `re`",
+                            );
+                        }
+                        pattern.push('\\');
+                        pattern.push(e);
+                    }
+                    None => {}
+                },
+                // named group, but not a lookbehind
+                '(' if chars.clone().take(3).collect::<String>().starts_with("?<")
+                    && !matches!(chars.clone().nth(2), Some('=' | '!')) =>
+                {
+                    chars.next();
+                    chars.next();
+                    pattern.push_str("(?P<");
+                }
+                // `[^]` matches any character, `[]` matches nothing
+                '[' if chars.clone().take(2).collect::<String>() == "^]" => {
+                    chars.next();
+                    chars.next();
+                    pattern.push_str("[\\s\\S]");
+                }
+                '[' if chars.peek() == Some(&']') => {
+                    chars.next();
+                    pattern.push_str("(?!)");
+                }
+                '"' => pattern.push_str("\\\""),
+                c => pattern.push(c),
+            }
+        }
+        (format!("r\"{}\"", pattern), flags.join(" | "))
+    }
+
+    /// Translate a template literal to an f-string, or to `str.format` if an expression cannot
+    /// be placed inside an f-string (quotes, backslashes and `#` are rejected before Python 3.12)
+    fn translate_template_literal(&self, template: &TemplateLiteral) -> String {
+        let expressions = template
+            .expressions
+            .iter()
+            .map(|expr| self.translate_expression(expr))
+            .collect::<Vec<_>>();
+        let escape_braces = !expressions.is_empty();
+        let quasis = template
+            .quasis
+            .iter()
+            .map(|quasi| self.translate_template_element(quasi.raw, escape_braces))
+            .collect::<Vec<_>>();
+
+        if expressions.is_empty() {
+            return format!("\"{}\"", quasis.concat());
+        }
+
+        let use_format = expressions
+            .iter()
+            .any(|expr| expr.contains(['"', '\'', '\\', '#']) || expr.starts_with('{'));
+        let mut body = quasis[0].clone();
+        for (expr, quasi) in expressions.iter().zip(&quasis[1..]) {
+            if use_format {
+                body.push_str("{}");
+            } else if expr.contains(':') {
+                // a top-level `:` would start a format spec
+                body.push_str(&format!("{{({})}}", expr));
+            } else {
+                body.push_str(&format!("{{{}}}", expr));
+            }
+            body.push_str(quasi);
+        }
+        if use_format {
+            format!("\"{}\".format({})", body, expressions.join(", "))
+        } else {
+            format!("f\"{}\"", body)
+        }
+    }
+
+    /// Convert the raw text of a template element to the body of a double-quoted Python string
+    fn translate_template_element(&self, raw: &str, escape_braces: bool) -> String {
+        let mut out = String::with_capacity(raw.len());
+        let mut chars = raw.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    // escapes with the same meaning in Python
+                    Some(
+                        e @ ('n' | 'r' | 't' | 'b' | 'f' | 'v' | '0' | 'x' | '\\' | '\'' | '"'),
+                    ) => {
+                        out.push('\\');
+                        out.push(e);
+                    }
+                    Some('u') if chars.peek() == Some(&'{') => {
+                        chars.next();
+                        let hex = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+                        let code = u32::from_str_radix(&hex, 16).unwrap_or_default();
+                        out.push_str(&format!("\\U{:08x}", code));
+                    }
+                    Some('u') => out.push_str("\\u"),
+                    // line continuation
+                    Some('\r') => {
+                        if chars.peek() == Some(&'\n') {
+                            chars.next();
+                        }
+                    }
+                    Some('\n' | '\u{2028}' | '\u{2029}') | None => {}
+                    Some('{') if escape_braces => out.push_str("{{"),
+                    Some('}') if escape_braces => out.push_str("}}"),
+                    // identity escapes such as \` and \$
+                    Some(e) => out.push(e),
+                },
+                '"' => out.push_str("\\\""),
+                '{' if escape_braces => out.push_str("{{"),
+                '}' if escape_braces => out.push_str("}}"),
+                '\r' => {
+                    if chars.peek() == Some(&'\n') {
+                        chars.next();
+                    }
+                    out.push_str("\\n");
+                }
+                '\n' => out.push_str("\\n"),
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn translate_parenthesized_expression(&self, parent_expr: &ParenthesizedExpression) -> String {
+        let expression = self.translate_expression(&parent_expr.expression);
+        // a lifted function is only a name, as in `(function () { ... })()`, and a conditional
+        // or an optional chain is parenthesized already
+        if matches!(
+            parent_expr.expression,
+            Expression::FunctionExpression(_)
+                | Expression::ConditionalExpression(_)
+                | Expression::ChainExpression(_)
+        ) {
+            return expression;
+        }
+        format!("({})", expression)
+    }
+
+    fn translate_unary_expression(&self, unary_expr: &UnaryExpression) -> String {
+        let operator = self.translate_unary_operator(unary_expr, &unary_expr.operator);
+        let argument = self.translate_expression(&unary_expr.argument);
+        if operator == "not " {
+            return format!("({}{})", operator, argument);
+        }
+        format!("{}{}", operator, argument)
+    }
+
+    fn translate_unary_operator(&self, node: &impl GetSpan, operator: &UnaryOperator) -> String {
+        match operator {
+            UnaryOperator::LogicalNot => "not ".to_string(),
+            UnaryOperator::UnaryPlus => "+".to_string(),
+            UnaryOperator::UnaryNegation => "-".to_string(),
+            UnaryOperator::BitwiseNot => "~".to_string(),
+            _ => self.unsupported(node, "unary operator"),
+        }
+    }
+
+    fn translate_computed_member_expression(&self, mem_expr: &ComputedMemberExpression) -> String {
+        let object = self.translate_expression(&mem_expr.object);
+        let expression = self.translate_expression(&mem_expr.expression);
+        format!("{}[{}]", object, expression)
+    }
+
+    /// `(b if a else c)`, the parentheses keep it whole inside other operators, a lambda before
+    /// `if` is parenthesized too so that it does not take the rest as its body
+    fn translate_conditional_expression(&self, cond: &ConditionalExpression) -> String {
+        let wrap_lambda = |code: String| {
+            if code.starts_with("lambda") {
+                format!("({})", code)
+            } else {
+                code
+            }
+        };
+        // an assignment in a branch is an expression giving the assigned value
+        let translate_branch = |expr: &Expression| match as_assignment(expr) {
+            Some(assign) => self.translate_assignment_value(assign),
+            None => self.translate_expression(expr),
+        };
+        let test = wrap_lambda(self.translate_expression(&cond.test));
+        let consequent = wrap_lambda(translate_branch(&cond.consequent));
+        let alternate = translate_branch(&cond.alternate);
+        format!("({} if {} else {})", consequent, test, alternate)
+    }
+
+    fn translate_logical_expression(&self, logic_expr: &LogicalExpression) -> String {
+        let left = self.translate_expression(&logic_expr.left);
+        let right = self.translate_expression(&logic_expr.right);
+        let operator = match logic_expr.operator {
+            LogicalOperator::Or => "or",
+            LogicalOperator::And => "and",
+            LogicalOperator::Coalesce => return self.translate_coalesce(left, right),
+        };
+        format!("{} {} {}", left, operator, right)
+    }
+
+    /// `a ?? b` is `b` only if `a` is `None`, a left side that is not a name is evaluated once
+    /// into a temporary
+    fn translate_coalesce(&self, left: String, right: String) -> String {
+        if is_simple_name(&left) {
+            return format!("({} if {} is not None else {})", left, left, right);
+        }
+        let name = self.generate_name("_value");
+        format!(
+            "({} if ({} := {}) is not None else {})",
+            name, name, left, right
+        )
+    }
+
+    /// `a?.b.c` is `None` if `a` is `None`, otherwise `a.b.c`
+    fn translate_chain_expression(&self, chain: &ChainExpression) -> String {
+        let (tests, mut code) = self.translate_chain(chain);
+        for test in tests.iter().rev() {
+            code = format!("(None if {} is None else {})", test, code);
+        }
+        code
+    }
+
+    /// The objects of the `?.` in a chain to compare with `None`, innermost first, and the
+    /// chain as if it had no `?.`. An object that is not a name is assigned to a temporary in
+    /// its test, and the temporary is read in its place, so that it is evaluated once.
+    fn translate_chain(&self, chain: &ChainExpression) -> (Vec<String>, String) {
+        let mut objects = vec![];
+        let mut expr = &chain.expression;
+        loop {
+            let (object, optional) = match expr {
+                Expression::StaticMemberExpression(member) => (&member.object, member.optional),
+                Expression::ComputedMemberExpression(member) => (&member.object, member.optional),
+                Expression::CallExpression(call) => (&call.callee, call.optional),
+                _ => break,
+            };
+            if optional {
+                objects.push(object);
+            }
+            expr = object;
+        }
+
+        let substituted = self.substitutions.borrow().len();
+        let mut tests = vec![];
+        for object in objects.into_iter().rev() {
+            let code = self.translate_expression(object);
+            if is_simple_name(&code) {
+                tests.push(code);
+                continue;
+            }
+            let name = self.generate_name("_value");
+            tests.push(format!("({} := {})", name, code));
+            self.substitutions.borrow_mut().push((object.span(), name));
+        }
+        let code = self.translate_expression(&chain.expression);
+        self.substitutions.borrow_mut().truncate(substituted);
+        (tests, code)
+    }
+
+    fn translate_call_expression(&self, call_expr: &CallExpression) -> String {
+        if let Some(code) = self.translate_regexp_call(call_expr) {
+            return code;
+        }
+        // the base class constructor
+        if let Expression::Super(_) = &call_expr.callee {
+            let arguments = call_expr
+                .arguments
+                .iter()
+                .map(|arg| self.translate_expression(arg))
+                .collect::<Vec<_>>();
+            return format!("super().__init__({})", arguments.join(", "));
+        }
+
+        let callee = self.translate_expression(&call_expr.callee);
+
+        let arguments = call_expr
+            .arguments
+            .iter()
+            .map(|arg| self.translate_expression(arg))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // 特殊判断：如果 callee 是 StaticMemberExpression，且 property 为 push
+        if let Expression::StaticMemberExpression(mem_expr) = &call_expr.callee {
+            if let Expression::Identifier(id) = &mem_expr.object {
+                if id.name == "console" && mem_expr.property.name == "log" {
+                    return format!("print({})", arguments);
+                }
+            }
+            if mem_expr.property.name == "push" {
+                let object = self.translate_expression(&mem_expr.object);
+                return format!("{}.append({})", object, arguments);
+            }
+        }
+
+        if let Expression::Identifier(id) = &call_expr.callee {
+            if id.name == "parseFloat" {
+                return format!("float({})", arguments);
+            }
+        }
+
+        format!("{}({})", callee, arguments)
+    }
+
+    /// `str.replace(regexp, ...)` and `regexp.test(str)` are mapped to the functions of `re`
+    fn translate_regexp_call(&self, call_expr: &CallExpression) -> Option<String> {
+        let Expression::StaticMemberExpression(mem_expr) = &call_expr.callee else {
+            return None;
+        };
+        match (
+            &mem_expr.object,
+            mem_expr.property.name,
+            &call_expr.arguments[..],
+        ) {
+            (Expression::RegExpLiteral(regexp), "test", [string]) => {
+                let (pattern, flags) = self.translate_regexp(regexp);
+                let string = self.translate_expression(string);
+                let mut args = vec![pattern, string];
+                if !flags.is_empty() {
+                    args.push(flags);
+                }
+                Some(format!("re.search({}) is not None", args.join(", ")))
+            }
+            (object, "replace", [Expression::RegExpLiteral(regexp), replacement]) => {
+                let (pattern, flags) = self.translate_regexp(regexp);
+                let object = self.translate_expression(object);
+                let replacement = self.translate_replacement(regexp, replacement);
+                let mut args = vec![pattern, replacement, object];
+                if !regexp.flags.contains('g') {
+                    args.push("count=1".to_string());
+                }
+                if !flags.is_empty() {
+                    args.push(format!("flags={}", flags));
+                }
+                Some(format!("re.sub({})", args.join(", ")))
+            }
+            _ => None,
+        }
+    }
+
+    /// The replacement of `str.replace` for `re.sub`: the `$` patterns of a string are mapped to
+    /// `\g<...>` references, and a function gets the arguments JavaScript passes instead of a
+    /// match object
+    fn translate_replacement(&self, regexp: &RegExpLiteral, replacement: &Expression) -> String {
+        match replacement {
+            Expression::StringLiteral(s) => {
+                let groups = capture_groups(regexp.pattern);
+                let (quote, text) = (&s.value[..1], &s.value[1..s.value.len() - 1]);
+                let mut code = String::with_capacity(s.value.len());
+                let mut chars = text.chars().peekable();
+                while let Some(c) = chars.next() {
+                    match c {
+                        // a backslash is an escape for `re.sub` too
+                        '\\' if chars.peek() == Some(&'\\') => {
+                            chars.next();
+                            code.push_str("\\\\\\\\");
+                        }
+                        '\\' => {
+                            code.push(c);
+                            code.extend(chars.next());
+                        }
+                        '$' => match chars.peek() {
+                            Some('$') => {
+                                chars.next();
+                                code.push('$');
+                            }
+                            Some('&') => {
+                                chars.next();
+                                code.push_str("\\\\g<0>");
+                            }
+                            Some('<') if text.contains('>') => {
+                                chars.next();
+                                let name = chars.by_ref().take_while(|&c| c != '>');
+                                code.push_str(&format!("\\\\g<{}>", name.collect::<String>()));
+                            }
+                            Some(d) if d.is_ascii_digit() => {
+                                // `$nn` refers to group nn if there is one, or else `$n` does
+                                let mut rest = chars.clone();
+                                let one = rest.next().and_then(|d| d.to_digit(10)).unwrap();
+                                let two = rest.next().and_then(|d| d.to_digit(10));
+                                match two.map(|two| one * 10 + two) {
+                                    Some(n) if n >= 1 && n as usize <= groups => {
+                                        chars.nth(1);
+                                        code.push_str(&format!("\\\\g<{}>", n));
+                                    }
+                                    _ if one >= 1 && one as usize <= groups => {
+                                        chars.next();
+                                        code.push_str(&format!("\\\\g<{}>", one));
+                                    }
+                                    _ => code.push('$'),
+                                }
+                            }
+                            Some('`' | '\'') => {
+                                self.warn(
+                                    replacement,
+                                    "the replacement patterns $` and $' are not supported",
+                                );
+                                code.push('$');
+                            }
+                            _ => code.push('$'),
+                        },
+                        c => code.push(c),
+                    }
+                }
+                format!("{}{}{}", quote, code, quote)
+            }
+            Expression::ArrowFunctionExpression(_) | Expression::FunctionExpression(_) => {
+                self.use_runtime(runtime::JS_REPLACER);
+                format!("js_replacer({})", self.translate_expression(replacement))
+            }
+            _ => {
+                self.warn(
+                    replacement,
+                    "the replacement is passed to `re.sub` as is, its `$` patterns are not mapped",
+                );
+                self.translate_expression(replacement)
+            }
+        }
+    }
+
+    fn translate_object_expression(&self, obj_expr: &ObjectExpression) -> String {
+        let properties = obj_expr
+            .properties
+            .iter()
+            .map(|prop| {
+                let key = self.translate_property_key(&prop.key);
+                let value = self.translate_expression(&prop.value);
+                format!("{}: {}", key, value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{{{}}}", properties)
+    }
+
+    /// The key of an object literal or pattern as a dictionary key
+    fn translate_property_key(&self, key: &PropertyKey) -> String {
+        match key {
+            PropertyKey::IdentifierName(id) => format!("\"{}\"", id.name),
+            PropertyKey::StringLiteral(s) => s.value.to_string(),
+            PropertyKey::NumericLiteral(n) => self.translate_numeric_literal(n),
+        }
+    }
+
+    fn translate_array_expression(&self, arr_expr: &ArrayExpression) -> String {
+        let elements = arr_expr
+            .elements
+            .iter()
+            .map(|element| match element {
+                ArrayExpressionElement::Elision(_) => "None".to_string(),
+                ArrayExpressionElement::Expression(expr) => self.translate_expression(expr),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("[{}]", elements)
+    }
+
+    fn translate_static_member_expression(&self, mem_expr: &StaticMemberExpression) -> String {
+        let object = self.translate_expression(&mem_expr.object);
+        let property = mangle(mem_expr.property.name);
+        if property == "length" {
+            return format!("len({})", object);
+        }
+        format!("{}.{}", object, property)
+    }
+
+    fn translate_binary_expression(&self, bin_expr: &BinaryExpression) -> String {
+        let lhs = self.translate_expression(&bin_expr.left);
+        let rhs = self.translate_expression(&bin_expr.right);
+        let op = self.translate_binary_operator(bin_expr, bin_expr.operator);
+        format!("{} {} {}", lhs, op, rhs)
+    }
+
+    /// An update whose value is used: a variable is updated with `:=`, which gives the new value
+    /// of a prefix update and is undone for the old value of a postfix update, a property is
+    /// updated by a runtime helper
+    ///
+    /// `:=` binds a local variable, so an arrow function updating a captured variable is a `def`
+    /// declaring it `nonlocal` or `global` rather than a `lambda`.
+    fn translate_update_expression(&self, update: &UpdateExpression) -> String {
+        let (operator, inverse, delta) = match update.operator {
+            UpdateOperator::Increment => ("+", "-", "1"),
+            UpdateOperator::Decrement => ("-", "+", "-1"),
+        };
+        let prefix = if update.prefix { "True" } else { "False" };
+        match &update.argument {
+            Expression::Identifier(id) => {
+                let name = mangle(id.name);
+                let assign = format!("({} := {} {} 1)", name, name, operator);
+                if update.prefix {
+                    assign
+                } else {
+                    format!("({} {} 1)", assign, inverse)
+                }
+            }
+            Expression::StaticMemberExpression(member) => {
+                self.use_runtime(runtime::JS_UPDATE_ATTR);
+                format!(
+                    "js_update_attr({}, \"{}\", {}, {})",
+                    self.translate_expression(&member.object),
+                    mangle(member.property.name),
+                    delta,
+                    prefix
+                )
+            }
+            Expression::ComputedMemberExpression(member) => {
+                self.use_runtime(runtime::JS_UPDATE_ITEM);
+                format!(
+                    "js_update_item({}, {}, {}, {})",
+                    self.translate_expression(&member.object),
+                    self.translate_expression(&member.expression),
+                    delta,
+                    prefix
+                )
+            }
+            argument => self.unsupported(argument, "update target"),
+        }
+    }
+
+    fn translate_assignment_expression(&self, assign_expr: &AssignmentExpression) -> String {
+        if let Some(code) = self.translate_logical_assignment_expression(assign_expr) {
+            return code;
+        }
+        let left = self.translate_assignment_target(&assign_expr.left);
+        let operator = self.translate_assign_operator(assign_expr, &assign_expr.operator);
+        let right = self.translate_expression(&assign_expr.right);
+        format!("{} {} {}", left, operator, right)
+    }
+
+    fn translate_assignment_target(&self, target: &AssignmentTarget) -> String {
+        match target {
+            AssignmentTarget::Identifier(id) => mangle(id.name),
+            AssignmentTarget::StaticMemberExpression(mem_expr) => {
+                self.translate_static_member_expression(mem_expr)
+            }
+            AssignmentTarget::ComputedMemberExpression(mem_expr) => {
+                self.translate_computed_member_expression(mem_expr)
+            }
+        }
+    }
+
+    /// An assignment whose value is used: a variable is assigned with `:=`, a property by a
+    /// runtime helper returning the value, the object and the key of a compound assignment to a
+    /// property are evaluated once into temporaries
+    fn translate_assignment_value(&self, assign_expr: &AssignmentExpression) -> String {
+        if let Some(code) = self.translate_logical_assignment_expression(assign_expr) {
+            return code;
+        }
+        let operator = self.translate_assign_operator(assign_expr, &assign_expr.operator);
+        let operator = operator.strip_suffix('=').filter(|op| !op.is_empty());
+        let mut right = self.translate_expression(&assign_expr.right);
+        if operator.is_some() && !is_simple_name(&right) && right.parse::<f64>().is_err() {
+            right = format!("({})", right);
+        }
+        let value = |read: String| match operator {
+            Some(operator) => format!("{} {} {}", read, operator, right),
+            None => right.clone(),
+        };
+        let once = |expr: &Expression| {
+            if operator.is_none() {
+                let code = self.translate_expression(expr);
+                (code.clone(), code)
+            } else {
+                self.translate_expression_inline_once(expr)
+            }
+        };
+        match &assign_expr.left {
+            AssignmentTarget::Identifier(id) => {
+                let name = mangle(id.name);
+                format!("({} := {})", name, value(name.clone()))
+            }
+            AssignmentTarget::StaticMemberExpression(member) => {
+                self.use_runtime(runtime::JS_ASSIGN_ATTR);
+                let (object, read) = once(&member.object);
+                let property = mangle(member.property.name);
+                format!(
+                    "js_assign_attr({}, \"{}\", {})",
+                    object,
+                    property,
+                    value(format!("{}.{}", read, property))
+                )
+            }
+            AssignmentTarget::ComputedMemberExpression(member) => {
+                self.use_runtime(runtime::JS_ASSIGN_ITEM);
+                let (object, read_object) = once(&member.object);
+                let (key, read_key) = once(&member.expression);
+                format!(
+                    "js_assign_item({}, {}, {})",
+                    object,
+                    key,
+                    value(format!("{}[{}]", read_object, read_key))
+                )
+            }
+        }
+    }
+
+    /// `a ||= b` whose value is used assigns with `:=` in the branch that assigns, a property
+    /// is assigned by a runtime helper returning the value, its object and key evaluated once
+    /// where they are first read
+    fn translate_logical_assignment_expression(
+        &self,
+        assign_expr: &AssignmentExpression,
+    ) -> Option<String> {
+        use AssignmentOperator::*;
+        if !matches!(
+            assign_expr.operator,
+            LogicalAnd | LogicalOr | LogicalNullish
+        ) {
+            return None;
+        }
+        let (read, assign) = match &assign_expr.left {
+            AssignmentTarget::Identifier(id) => {
+                let name = mangle(id.name);
+                let assign = format!(
+                    "({} := {})",
+                    name,
+                    self.translate_expression(&assign_expr.right)
+                );
+                (name, assign)
+            }
+            AssignmentTarget::StaticMemberExpression(member) => {
+                self.use_runtime(runtime::JS_ASSIGN_ATTR);
+                let (object, read_object) = self.translate_expression_inline_once(&member.object);
+                let property = mangle(member.property.name);
+                let assign = format!(
+                    "js_assign_attr({}, \"{}\", {})",
+                    read_object,
+                    property,
+                    self.translate_expression(&assign_expr.right)
+                );
+                (format!("{}.{}", object, property), assign)
+            }
+            AssignmentTarget::ComputedMemberExpression(member) => {
+                self.use_runtime(runtime::JS_ASSIGN_ITEM);
+                let (object, read_object) = self.translate_expression_inline_once(&member.object);
+                let (key, read_key) = self.translate_expression_inline_once(&member.expression);
+                let assign = format!(
+                    "js_assign_item({}, {}, {})",
+                    read_object,
+                    read_key,
+                    self.translate_expression(&assign_expr.right)
+                );
+                (format!("{}[{}]", object, key), assign)
+            }
+        };
+        Some(match assign_expr.operator {
+            LogicalAnd => format!("({} and {})", read, assign),
+            LogicalOr => format!("({} or {})", read, assign),
+            _ => self.translate_coalesce(read, assign),
+        })
+    }
+
+    /// A logical assignment statement assigns under an `if`, the object and the key of a member
+    /// target are evaluated once
+    fn translate_logical_assignment_statement(&self, assign_expr: &AssignmentExpression) -> String {
+        let left = match &assign_expr.left {
+            AssignmentTarget::Identifier(id) => mangle(id.name),
+            AssignmentTarget::StaticMemberExpression(member) => format!(
+                "{}.{}",
+                self.translate_expression_once(&member.object),
+                mangle(member.property.name)
             ),
+            AssignmentTarget::ComputedMemberExpression(member) => format!(
+                "{}[{}]",
+                self.translate_expression_once(&member.object),
+                self.translate_expression_once(&member.expression)
+            ),
+        };
+        let test = match assign_expr.operator {
+            AssignmentOperator::LogicalAnd => left.clone(),
+            AssignmentOperator::LogicalOr => format!("not {}", left),
+            _ => format!("{} is None", left),
+        };
+        let right = self.translate_expression(&assign_expr.right);
+        format!(
+            "if {}:\n{}",
+            test,
+            make_indent(&format!("{} = {}", left, right), self.indent)
+        )
+    }
+
+    /// An expression that is read more than once, evaluated into a temporary before the
+    /// statement unless reading it again has no effect
+    fn translate_expression_once(&self, expr: &Expression) -> String {
+        let code = self.translate_expression(expr);
+        if is_pure(expr) {
+            return code;
+        }
+        let name = self.generate_name("_value");
+        self.hoist(format!("{} = {}", name, code));
+        name
+    }
+
+    /// An expression that is read more than once within an expression, evaluated into a
+    /// temporary with `:=` where it is first read, so that it is only evaluated if and when the
+    /// expression around it is, returns the code that reads it first and the code that reads it
+    /// again
+    fn translate_expression_inline_once(&self, expr: &Expression) -> (String, String) {
+        let code = self.translate_expression(expr);
+        if is_pure(expr) {
+            return (code.clone(), code);
+        }
+        let name = self.generate_name("_value");
+        (format!("({} := {})", name, code), name)
+    }
+
+    fn translate_assign_operator(
+        &self,
+        node: &impl GetSpan,
+        operator: &AssignmentOperator,
+    ) -> String {
+        use AssignmentOperator::*;
+        match operator {
+            ShiftRightZeroFill | LogicalAnd | LogicalOr | LogicalNullish => {
+                self.unsupported(node, "assignment operator")
+            }
             o => serde_json::to_string(o)
                 .unwrap()
                 .trim_matches('"')
@@ -329,7 +2567,7 @@ impl Ast2Py {
         }
     }
 
-    fn translate_binary_operator(&self, operator: BinaryOperator) -> String {
+    fn translate_binary_operator(&self, node: &impl GetSpan, operator: BinaryOperator) -> String {
         use BinaryOperator::*;
         match operator {
             StrictEquality => "is",
@@ -350,18 +2588,117 @@ impl Ast2Py {
             ShiftLeft => "<<",
             ShiftRight => ">>",
             BitwiseAnd => "&",
-            _ => unimplemented!(
-                "unsupported binary operator {:?}",
-                serde_json::to_string(&operator).unwrap()
-            ),
+            Exponential => "**",
+            _ => self.unsupported(node, "binary operator"),
         }
         .to_string()
     }
 }
 
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+/// The number of capturing groups of a regular expression pattern
+fn capture_groups(pattern: &str) -> usize {
+    let mut count = 0;
+    let mut in_class = false;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            // `(?<name>...)` captures, the other `(?...)` groups do not
+            '(' if !in_class => {
+                let mut rest = chars.clone();
+                if rest.next() != Some('?')
+                    || rest.next() == Some('<') && !matches!(rest.next(), Some('=' | '!'))
+                {
+                    count += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    count
+}
+
+/// The value of an integer literal, possibly negated
+/// Whether the cases of a `switch` compare with both booleans and numbers
+fn mixes_bool_and_number(groups: &[SwitchGroup]) -> bool {
+    let tests = || groups.iter().flat_map(|g| g.tests.iter().flatten());
+    tests().any(|test| matches!(test, Expression::BooleanLiteral(_)))
+        && tests().any(|test| {
+            matches!(test, Expression::NumericLiteral(_)) || integer_literal(test).is_some()
+        })
+}
+
+fn integer_literal(expr: &Expression) -> Option<i64> {
+    match expr {
+        Expression::NumericLiteral(num)
+            if num.value.fract() == 0.0 && num.value.abs() <= MAX_SAFE_INTEGER =>
+        {
+            Some(num.value as i64)
+        }
+        Expression::UnaryExpression(unary) if unary.operator == UnaryOperator::UnaryNegation => {
+            integer_literal(&unary.argument).map(|n| -n)
+        }
+        _ => None,
+    }
+}
+
+/// Whether reading an expression again has no effect and gives the same value
+fn is_pure(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::Identifier(_)
+            | Expression::ThisExpression(_)
+            | Expression::NumericLiteral(_)
+            | Expression::StringLiteral(_)
+    )
+}
+
+/// Whether translated code ends with a `break` or a `continue` that is not nested in another
+/// statement, after which code is unreachable
+fn ends_with_jump(code: &str) -> bool {
+    matches!(code.lines().last(), Some("break" | "continue"))
+}
+
+/// The assignment an expression is, possibly parenthesized
+fn as_assignment<'a, 'b>(expr: &'b Expression<'a>) -> Option<&'b AssignmentExpression<'a>> {
+    match expr {
+        Expression::AssignmentExpression(assign) => Some(assign),
+        Expression::ParenthesizedExpression(e) => as_assignment(&e.expression),
+        _ => None,
+    }
+}
+
+/// Whether translated code is a plain name, which can be read again without evaluating
+/// anything
+fn is_simple_name(code: &str) -> bool {
+    let mut chars = code.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
 #[cfg(test)]
 mod test {
     use js2py_parser::Parser;
+    /// Run translated code with `python3` and return its output, or `None` if there is no
+    /// `python3` to run it with
+    fn run_python(code: &str) -> Option<String> {
+        let output = std::process::Command::new("python3")
+            .args(["-c", code])
+            .output()
+            .ok()?;
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Some(String::from_utf8(output.stdout).unwrap())
+    }
     fn assert_translate(source: &str, expected: &str) {
         let mut parser = Parser::new(source);
         let ast = parser.parse().unwrap();
@@ -385,6 +2722,11 @@ mod test {
         let source = "while (a) b";
         let expected = "while a:\n    b";
         assert_translate(source, expected);
+        // code hoisted from the test runs before every check
+        assert_translate(
+            "while (ok(function (x) { return x })) { if (a) continue; f() }",
+            "while True:\n    def _function_1(x):\n        return x\n    if not (ok(_function_1)):\n        break\n    if a:\n        continue\n    f()",
+        );
     }
     #[test]
     fn test_parenthesized_expression() {
@@ -409,6 +2751,656 @@ mod test {
         assert_translate(source, expected);
     }
     #[test]
+    fn test_template_literal() {
+        assert_translate("`Hello`", "\"Hello\"");
+        assert_translate(
+            "`Hello ${name}, you have ${n + 1} items`",
+            "f\"Hello {name}, you have {n + 1} items\"",
+        );
+        assert_translate("`{${a}}\\n\"\\``", "f\"{{{a}}}\\n\\\"`\"");
+        assert_translate("`a ${b['c']}`", "\"a {}\".format(b['c'])");
+        assert_translate("`line1\nline2`", "\"line1\\nline2\"");
+    }
+    #[test]
+    fn test_regexp_literal() {
+        assert_translate(
+            "s.replace(/\\s+/g, \" \")",
+            "import re\n\nre.sub(r\"\\s+\", \" \", s)",
+        );
+        assert_translate(
+            "/^a\\/b$/i.test(s)",
+            "import re\n\nre.search(r\"^a/b$\", s, re.I) is not None",
+        );
+        assert_translate(
+            "p = /(?<y>\\d+)-\\k<y>/ms",
+            "import re\n\np = re.compile(r\"(?P<y>\\d+)-(?P=y)\", re.M | re.S)",
+        );
+        // `$` patterns of the replacement
+        assert_translate(
+            "s.replace(/(\\w+) (\\w+)/g, '$2 $1 $& $$1 $3 \\\\ ')",
+            "import re\n\nre.sub(r\"(\\w+) (\\w+)\", '\\\\g<2> \\\\g<1> \\\\g<0> $1 $3 \\\\\\\\ ', s)",
+        );
+        assert_translate(
+            "s.replace(/(?<y>\\d+)/, \"[$<y>]\")",
+            "import re\n\nre.sub(r\"(?P<y>\\d+)\", \"[\\\\g<y>]\", s, count=1)",
+        );
+        assert_translate(
+            "s.replace(/(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)/, '$10 $11')",
+            "import re\n\nre.sub(r\"(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\", '\\\\g<10> \\\\g<1>1', s, count=1)",
+        );
+        // a replacement function gets the match and the groups
+        assert_translate(
+            "s.replace(/a(b)/g, (m, b) => b + m)",
+            "import re\n\ndef js_replacer(fn):\n    code = fn.__code__\n    count = None if code.co_flags & 0x04 else code.co_argcount  # 0x04: *args\n    def replace(match):\n        args = (match.group(0), *match.groups(), match.start(), match.string)\n        return str(fn(*args[:count]))\n    return replace\n\nre.sub(r\"a(b)\", js_replacer(lambda m, b: b + m), s)",
+        );
+        let ast = Parser::new("a;\nb = /a/y").parse().unwrap();
+        let ret = super::Ast2Py::default().build(&ast);
+        assert_eq!(ret.warnings.len(), 1);
+        assert!(ret.warnings[0].starts_with("2:5: /a/y: "));
+    }
+    #[test]
+    fn test_identifier_mangling() {
+        assert_translate("var $el = item2.$parent", "_S_el = item2._S_parent");
+        assert_translate("function f(lambda) {}", "def f(lambda_K_):\n    pass");
+    }
+    #[test]
+    fn test_numeric_literal() {
+        assert_translate(
+            "[0xFF, 0b1010, 0o777, 1_000]",
+            "[0xFF, 0b1010, 0o777, 1_000]",
+        );
+        assert_translate("[5.0, 1e3, .5, 1.5e-3, 10n]", "[5, 1000, .5, 1.5e-3, 10]");
+    }
+    #[test]
+    fn test_error_recovery() {
+        let source = "a = 1;\nb c d;\nif (+) {\n  g()\n}\nfunction f() {\n  return )\n}";
+        let (ast, errors) = Parser::new(source).parse_with_recovery();
+        assert_eq!(errors.len(), 3);
+        let python_code = super::Ast2Py::default().build(&ast).code;
+        assert_eq!(
+            python_code,
+            "a = 1\n# TODO: syntax error\n# b c d;\n# TODO: syntax error\n# +\nif None:\n    g()\ndef f():\n    # TODO: syntax error\n    # return )"
+        );
+    }
+    #[test]
+    fn test_arrow_function() {
+        assert_translate("f = x => x * 2", "f = lambda x: x * 2");
+        assert_translate("g(() => 1)", "g(lambda: 1)");
+        assert_translate(
+            "const add = (a, b) => { return a + b }",
+            "def add(a, b):\n    return a + b",
+        );
+        assert_translate(
+            "if (a) {\n  items.forEach((x, i) => {\n    print(i, x)\n  })\n}",
+            "if a:\n    def _arrow_1(x, i):\n        print(i, x)\n    items.forEach(_arrow_1)",
+        );
+        // the block body of an inner arrow function keeps the outer parameters in scope
+        assert_translate(
+            "r = xs.map(x => ys.map(y => { return x + y }))",
+            "def _arrow_2(x):\n    def _arrow_1(y):\n        return x + y\n    return ys.map(_arrow_1)\nr = xs.map(_arrow_2)",
+        );
+        // an assignment to a captured variable is declared, and its value is returned
+        assert_translate(
+            "h(() => a = 1)",
+            "def _arrow_1():\n    global a\n    a = 1\n    return a\nh(_arrow_1)",
+        );
+        assert_translate(
+            "h(() => o.n += 1)",
+            "def _arrow_1():\n    o.n += 1\n    return o.n\nh(_arrow_1)",
+        );
+        assert_translate(
+            "let total = 0; each(xs, x => { total += x })",
+            "total = 0\ndef _arrow_1(x):\n    global total\n    total += x\neach(xs, _arrow_1)",
+        );
+        assert_translate(
+            "function sum(xs) { let total = 0; each(xs, x => { let y = x; total += y }); return total }",
+            "def sum(xs):\n    total = 0\n    def _arrow_1(x):\n        nonlocal total\n        y = x\n        total += y\n    each(xs, _arrow_1)\n    return total",
+        );
+    }
+    #[test]
+    fn test_function_expression() {
+        assert_translate(
+            "setTimeout(function () {\n  tick()\n}, 100)",
+            "def _function_1():\n    tick()\nsetTimeout(_function_1, 100)",
+        );
+        assert_translate(
+            "(function () { var x = 1; print(x) })()",
+            "def _function_1():\n    x = 1\n    print(x)\n_function_1()",
+        );
+        assert_translate(
+            "var f = function (a) { return a }",
+            "def f(a):\n    return a",
+        );
+        assert_translate(
+            "var fact = function f(n) { return f(n - 1) }",
+            "def _function_1(n):\n    f = _function_1\n    return f(n - 1)\nfact = _function_1",
+        );
+        // the name of a function expression does not leak out of it
+        assert_translate(
+            "var f = 1; g(function f() { return 2 }); use(f)",
+            "f = 1\ndef _function_1():\n    return 2\ng(_function_1)\nuse(f)",
+        );
+        assert_translate(
+            "btn.on('click', e => handle(function () { done(e) }))",
+            "def _arrow_2(e):\n    def _function_1():\n        done(e)\n    return handle(_function_1)\nbtn.on('click', _arrow_2)",
+        );
+    }
+    #[test]
+    fn test_class() {
+        assert_translate("class A {}", "class A:\n    pass");
+        assert_translate(
+            "class Point { x = 0; static origin = null; constructor(y) { this.y = y } norm() { return this.x + this.y } }",
+            "class Point:\n    origin = None\n    def __init__(self, y):\n        self.x = 0\n        self.y = y\n    def norm(self):\n        return self.x + self.y",
+        );
+        assert_translate(
+            "class B extends A { tag = 'b'; constructor(a) { super(a); init(this) } static make() { return 1 } }",
+            "class B(A):\n    def __init__(self, a):\n        super().__init__(a)\n        self.tag = 'b'\n        init(self)\n    @staticmethod\n    def make():\n        return 1",
+        );
+        assert_translate(
+            "class C extends A { n; describe() { return super.describe() } }",
+            "class C(A):\n    def __init__(self, *args, **kwargs):\n        super().__init__(*args, **kwargs)\n        self.n = None\n    def describe(self):\n        return super().describe()",
+        );
+        assert_translate(
+            "class T { set v(x) { this._v = x } get v() { return this._v } set w(x) {} }",
+            "class T:\n    @property\n    def v(self):\n        return self._v\n    @v.setter\n    def v(self, x):\n        self._v = x\n    w = property()\n    @w.setter\n    def w(self, x):\n        pass",
+        );
+        assert_translate(
+            "class S { static get count() { return S.n } static set count(n) { S.n = n } }",
+            "class js_classproperty:\n    def __init__(self, fget):\n        self.fget = fget\n    def __get__(self, obj, cls=None):\n        return self.fget()\n\nclass S:\n    @js_classproperty\n    def count():\n        return S.n\n    # TODO: static setter\n    # static set count(n) { S.n = n }",
+        );
+        assert_translate(
+            "const K = class { get() { return 1 } }",
+            "class K:\n    def get(self):\n        return 1",
+        );
+        assert_translate(
+            "register(class {})",
+            "class _class_1:\n    pass\nregister(_class_1)",
+        );
+    }
+    #[test]
+    fn test_try_statement() {
+        assert_translate(
+            "try { f() } catch (e) { log(e.message) } finally { done() }",
+            "try:\n    f()\nexcept Exception as e:\n    log(e.message)\nfinally:\n    done()",
+        );
+        assert_translate(
+            "try {} catch {}",
+            "try:\n    pass\nexcept Exception:\n    pass",
+        );
+        assert_translate(
+            "try { f() } finally {}",
+            "try:\n    f()\nfinally:\n    pass",
+        );
+    }
+    #[test]
+    fn test_throw_statement() {
+        let js_error = super::runtime::JS_ERROR;
+        assert_translate(
+            "throw 'oops'",
+            &format!("{}\n\nraise JSError('oops')", js_error),
+        );
+        // a new `Error` is not wrapped again
+        assert_translate(
+            "throw new Error('oops')",
+            &format!("{}\n\nraise JSError('oops')", js_error),
+        );
+        assert_translate(
+            "try { f() } catch (e) { if (retry) throw e }",
+            "try:\n    f()\nexcept Exception as e:\n    if retry:\n        raise e",
+        );
+        assert_translate(
+            "try {} catch (e) {} throw e",
+            &format!(
+                "{}\n\ntry:\n    pass\nexcept Exception as e:\n    pass\nraise JSError(e)",
+                js_error
+            ),
+        );
+        // a class extending `Error` is a `JSError`, its instances are not wrapped
+        assert_translate(
+            "class MyError extends Error {} class Oops extends MyError {} throw new Oops('x')",
+            &format!(
+                "{}\n\nclass MyError(JSError):\n    pass\nclass Oops(MyError):\n    pass\nraise Oops('x')",
+                js_error
+            ),
+        );
+        let source = "class MyError extends Error { constructor(m) { super(m); this.code = 1 } }\ntry { throw new MyError('x') } catch (e) { console.log(e.message, e.code) }";
+        let ast = Parser::new(source).parse().unwrap();
+        let code = super::Ast2Py::default().build(&ast).code;
+        if let Some(output) = run_python(&code) {
+            assert_eq!(output, "x 1\n");
+        }
+    }
+    #[test]
+    fn test_for_range() {
+        assert_translate(
+            "for (let i = 0; i < n; i++) { print(i) }",
+            "for i in range(n):\n    print(i)",
+        );
+        assert_translate(
+            "for (let i = 1; i <= 10; i += 2) {}",
+            "for i in range(1, 11, 2):\n    pass",
+        );
+        assert_translate(
+            "for (let i = n; i >= 0; --i) { if (skip(i)) continue; print(i) }",
+            "for i in range(n, -1, -1):\n    if skip(i):\n        continue\n    print(i)",
+        );
+        assert_translate(
+            "for (let i = a; i > b; i -= 3) f(i)",
+            "for i in range(a, b, -3):\n    f(i)",
+        );
+    }
+    #[test]
+    fn test_for_while() {
+        // the loop variable is changed in the body
+        assert_translate(
+            "for (let i = 0; i < n; i++) { if (f(i)) { i++; continue } g(i) }",
+            "i = 0\nwhile i < n:\n    if f(i):\n        i += 1\n        i += 1\n        continue\n    g(i)\n    i += 1",
+        );
+        // the bound may change in the body
+        assert_translate(
+            "for (let i = 0; i < n; i++) n = g(i)",
+            "i = 0\nwhile i < n:\n    n = g(i)\n    i += 1",
+        );
+        assert_translate(
+            "for (var i = 0; i < 3; i++) {}",
+            "i = 0\nwhile i < 3:\n    i += 1",
+        );
+        assert_translate(
+            "for (i = 0, j = n; i < j; i++, j--) { while (x) { continue } continue }",
+            "i = 0\nj = n\nwhile i < j:\n    while x:\n        continue\n    i += 1\n    j -= 1\n    continue",
+        );
+        assert_translate("for (;;) {}", "while True:\n    pass");
+        assert_translate(
+            "for (let i = 0; i < n; i = next(i)) {}",
+            "i = 0\nwhile i < n:\n    i = next(i)",
+        );
+        // code hoisted from the test runs before every check
+        assert_translate(
+            "for (i = 0; ok(i, function (x) { return x }); i++) f(i)",
+            "i = 0\nwhile True:\n    def _function_1(x):\n        return x\n    if not (ok(i, _function_1)):\n        break\n    f(i)\n    i += 1",
+        );
+    }
+    #[test]
+    fn test_for_in_of() {
+        assert_translate(
+            "for (const k in obj) print(k, obj[k])",
+            &format!(
+                "{}\n\nfor k in js_keys(obj):\n    print(k, obj[k])",
+                super::runtime::JS_KEYS
+            ),
+        );
+        assert_translate(
+            "for (var v of [1, 2]) { if (!v) continue; f(v) }",
+            "for v in [1, 2]:\n    if (not v):\n        continue\n    f(v)",
+        );
+        assert_translate("for (a.b of xs) {}", "for a.b in xs:\n    pass");
+
+        let ast = Parser::new("for (let i in arr) {}").parse().unwrap();
+        let code = super::Ast2Py::default()
+            .with_string_keys(true)
+            .build(&ast)
+            .code;
+        assert_eq!(
+            code,
+            format!(
+                "{}\n\nfor i in js_string_keys(arr):\n    pass",
+                super::runtime::JS_STRING_KEYS
+            )
+        );
+    }
+    #[test]
+    fn test_switch_match() {
+        assert_translate(
+            "switch (op) { case '+': return a + b; case '-': case '~': f(); break; default: g() }",
+            "match op:\n    case '+':\n        return a + b\n    case '-' | '~':\n        f()\n    case _:\n        g()",
+        );
+        assert_translate(
+            "switch (n) { default: break; case -1: case null: h(); break }",
+            "match n:\n    case -1 | None:\n        h()\n    case _:\n        pass",
+        );
+    }
+    #[test]
+    fn test_switch_chain() {
+        // the tests are not literals
+        assert_translate(
+            "switch (x) { case A: a(); break; case B: b() }",
+            "if x == A:\n    a()\nelif x == B:\n    b()",
+        );
+        // fall-through and `default` in the middle
+        assert_translate(
+            "switch (f(x)) { case 1: a(); default: d(); case 2: b(); break; case 3: c() }",
+            "_switch_1 = f(x)\nif _switch_1 == 1:\n    a()\n    d()\n    b()\nelif _switch_1 == 2:\n    b()\nelif _switch_1 == 3:\n    c()\nelse:\n    d()\n    b()",
+        );
+        // a `break` in a nested loop does not leave the `switch`
+        assert_translate(
+            "switch (x) { case 1: while (y) { if (z) break; } a(); case 2: b() }",
+            "if x == 1:\n    while y:\n        if z:\n            break\n    a()\n    b()\nelif x == 2:\n    b()",
+        );
+        // `case 1:` would match `True` in a `match` statement
+        assert_translate(
+            "switch (x) { case 1: a(); break; case true: b(); break }",
+            "if x == 1 and x is not True:\n    a()\nelif x is True:\n    b()",
+        );
+        let source = "function f(x) { switch (x) { case 0: return 'zero'; case 1: return 'one'; case false: return 'false'; case true: return 'true' } }\nconsole.log(f(true), f(1), f(false), f(0));";
+        let ast = Parser::new(source).parse().unwrap();
+        let code = super::Ast2Py::default().build(&ast).code;
+        if let Some(output) = run_python(&code) {
+            assert_eq!(output, "true one false zero\n");
+        }
+    }
+    #[test]
+    fn test_switch_loop() {
+        assert_translate(
+            "switch (x) { case 1: if (y) break; a(); default: b() }",
+            "if x == 1:\n    _case_1 = 0\nelse:\n    _case_1 = 1\nwhile True:\n    if _case_1 <= 0:\n        if y:\n            break\n        a()\n    if _case_1 <= 1:\n        b()\n    break",
+        );
+        assert_translate(
+            "for (;;) { switch (x) { case 1: if (y) continue; if (z) break; a() } f() }",
+            "_continue_2 = False\nwhile True:\n    if x == 1:\n        _case_1 = 0\n    else:\n        _case_1 = 1\n    while True:\n        if _case_1 <= 0:\n            if y:\n                _continue_2 = True\n                break\n            if z:\n                break\n            a()\n        break\n    if _continue_2:\n        _continue_2 = False\n        continue\n    f()",
+        );
+    }
+    #[test]
+    fn test_do_while() {
+        assert_translate(
+            "do { i += 1 } while (i < n && ok)",
+            "while True:\n    i += 1\n    if not (i < n and ok):\n        break",
+        );
+        assert_translate(
+            "do { if (skip()) continue; while (a) { break } if (b) break; f() } while (more())",
+            "while True:\n    if skip():\n        if not (more()):\n            break\n        continue\n    while a:\n        break\n    if b:\n        break\n    f()\n    if not (more()):\n        break",
+        );
+        assert_translate(
+            "do ; while (x)",
+            "while True:\n    if not (x):\n        break",
+        );
+    }
+    #[test]
+    fn test_labeled_statement() {
+        // an unused label is dropped
+        assert_translate("a: while (x) { break }", "while x:\n    break");
+        assert_translate(
+            "outer: for (const a of xs) { for (const b of ys) { if (b) continue outer; if (a) break outer; f(a, b) } g(a) }",
+            "_continue_1 = False\n_break_2 = False\nfor a in xs:\n    for b in ys:\n        if b:\n            _continue_1 = True\n            break\n        if a:\n            _break_2 = True\n            break\n        f(a, b)\n    if _continue_1:\n        _continue_1 = False\n        continue\n    if _break_2:\n        break\n    g(a)",
+        );
+        // the update of the outer loop runs before it continues
+        assert_translate(
+            "outer: for (i = 0; i < n; i += 1) { while (x) { while (y) { continue outer } } }",
+            "_continue_1 = False\ni = 0\nwhile i < n:\n    while x:\n        while y:\n            _continue_1 = True\n            break\n        if _continue_1:\n            break\n    if _continue_1:\n        _continue_1 = False\n        i += 1\n        continue\n    i += 1",
+        );
+        assert_translate(
+            "block: { if (a) break block; f() }",
+            "while True:\n    if a:\n        break\n    f()\n    break",
+        );
+        // an unlabeled `break` in a labeled block leaves the enclosing loop
+        assert_translate(
+            "while (x) { b: { if (a) break b; break } }",
+            "_break_1 = False\nwhile x:\n    while True:\n        if a:\n            break\n        _break_1 = True\n        break\n    if _break_1:\n        break",
+        );
+    }
+    #[test]
+    fn test_conditional_expression() {
+        assert_translate("x = a ? b : c;", "x = (b if a else c)");
+        // nested to the right
+        assert_translate(
+            "x = a ? b : c ? d : e;",
+            "x = (b if a else (d if c else e))",
+        );
+        assert_translate(
+            "x = a ? b ? 1 : 2 : 3;",
+            "x = ((1 if b else 2) if a else 3)",
+        );
+        assert_translate("x = 1 + (a ? 2 : 3) * 4;", "x = 1 + (2 if a else 3) * 4");
+        assert_translate("x = a ? b : c + 1;", "x = (b if a else c + 1)");
+        assert_translate(
+            "f = a ? () => 1 : () => 2;",
+            "f = ((lambda: 1) if a else lambda: 2)",
+        );
+        // assignments in the branches
+        assert_translate("a ? b = 1 : c = 2;", "if a:\n    b = 1\nelse:\n    c = 2");
+        assert_translate(
+            "a ? (o.p += 1) : f();",
+            "if a:\n    o.p += 1\nelse:\n    f()",
+        );
+        assert_translate("x = a ? b = 1 : c;", "x = ((b := 1) if a else c)");
+        assert_translate(
+            "x = a ? b : (b *= c + 1);",
+            "x = (b if a else (b := b * (c + 1)))",
+        );
+        assert_translate(
+            "x = a ? o.p = 1 : f().q[k] -= 2;",
+            &format!(
+                "{}\n\n{}\n\nx = (js_assign_attr(o, \"p\", 1) if a else js_assign_item((_value_1 := f().q), k, _value_1[k] - 2))",
+                super::runtime::JS_ASSIGN_ATTR,
+                super::runtime::JS_ASSIGN_ITEM
+            ),
+        );
+    }
+    #[test]
+    fn test_exponentiation_and_coalesce() {
+        assert_translate("x = 2 ** 3 ** 2; x **= 2;", "x = 2 ** 3 ** 2\nx **= 2");
+        assert_translate("x = a ?? 1;", "x = (a if a is not None else 1)");
+        // the left side is evaluated once
+        assert_translate(
+            "x = f() ?? g();",
+            "x = (_value_1 if (_value_1 := f()) is not None else g())",
+        );
+    }
+    #[test]
+    fn test_logical_assignment() {
+        assert_translate("a ||= 1;", "if not a:\n    a = 1");
+        assert_translate("a &&= 1;", "if a:\n    a = 1");
+        assert_translate("o.p ??= [];", "if o.p is None:\n    o.p = []");
+        assert_translate(
+            "f().p[i + 1] ||= 1;",
+            "_value_1 = f().p\n_value_2 = i + 1\nif not _value_1[_value_2]:\n    _value_1[_value_2] = 1",
+        );
+        assert_translate("x = a ||= 1;", "x = (a or (a := 1))");
+        assert_translate("x = a ??= 1;", "x = (a if a is not None else (a := 1))");
+        // a property whose value is used
+        assert_translate(
+            "x = o.p ||= 1;",
+            &format!(
+                "{}\n\nx = (o.p or js_assign_attr(o, \"p\", 1))",
+                super::runtime::JS_ASSIGN_ATTR
+            ),
+        );
+        assert_translate(
+            "f(cache[k] ??= []);",
+            &format!(
+                "{}\n\nf((_value_1 if (_value_1 := cache[k]) is not None else js_assign_item(cache, k, [])))",
+                super::runtime::JS_ASSIGN_ITEM
+            ),
+        );
+        let js_assign_item = super::runtime::JS_ASSIGN_ITEM;
+        assert_translate(
+            "x = g().p[i + 1] &&= 0;",
+            &format!(
+                "{}\n\nx = ((_value_1 := g().p)[(_value_2 := i + 1)] and js_assign_item(_value_1, _value_2, 0))",
+                js_assign_item
+            ),
+        );
+        // the object is only evaluated when the assignment is
+        assert_translate(
+            "x = a ? g().p[k] ||= 1 : 0;",
+            &format!(
+                "{}\n\nx = (((_value_1 := g().p)[k] or js_assign_item(_value_1, k, 1)) if a else 0)",
+                js_assign_item
+            ),
+        );
+        assert_translate(
+            "y = ok && (h().q ??= 2);",
+            &format!(
+                "{}\n\ny = ok and ((_value_2 if (_value_2 := (_value_1 := h()).q) is not None else js_assign_attr(_value_1, \"q\", 2)))",
+                super::runtime::JS_ASSIGN_ATTR
+            ),
+        );
+        assert_translate(
+            "while (g().p[i] ||= 0) i++;",
+            &format!(
+                "{}\n\nwhile ((_value_1 := g().p)[i] or js_assign_item(_value_1, i, 0)):\n    i += 1",
+                js_assign_item
+            ),
+        );
+        let source = "class O { constructor() { this.p = 0 } }\nconst cache = [null]; const o = new O();\nconst a = cache[0] ??= [1];\nconst b = cache[0] ??= [2];\nconsole.log(a === b, o.p ||= 5, o.p &&= 6, o.p);";
+        let ast = Parser::new(source).parse().unwrap();
+        let code = super::Ast2Py::default().build(&ast).code;
+        if let Some(output) = run_python(&code) {
+            assert_eq!(output, "True 5 6 6\n");
+        }
+    }
+    #[test]
+    fn test_optional_chaining() {
+        assert_translate(
+            "x = user?.profile?.name;",
+            "x = (None if user is None else (None if (_value_1 := user.profile) is None else _value_1.name))",
+        );
+        // every intermediate is evaluated once, and the rest of the chain is skipped too
+        assert_translate(
+            "x = f()?.items[0].title;",
+            "x = (None if (_value_1 := f()) is None else _value_1.items[0].title)",
+        );
+        assert_translate("x = a?.[i];", "x = (None if a is None else a[i])");
+        assert_translate("x = (a?.b).c;", "x = (None if a is None else a.b).c");
+        assert_translate("cb?.();", "if cb is not None:\n    cb()");
+        assert_translate(
+            "a.b?.c?.(1);",
+            "if (_value_1 := a.b) is not None and (_value_2 := _value_1.c) is not None:\n    _value_2(1)",
+        );
+    }
+    #[test]
+    fn test_update_expression() {
+        assert_translate("i++; --j;", "i += 1\nj -= 1");
+        assert_translate("x = arr[i++];", "x = arr[((i := i + 1) - 1)]");
+        assert_translate("x = --i * 2;", "x = (i := i - 1) * 2");
+        assert_translate(
+            "x = o.n++;",
+            &format!(
+                "{}\n\nx = js_update_attr(o, \"n\", 1, False)",
+                super::runtime::JS_UPDATE_ATTR
+            ),
+        );
+        assert_translate(
+            "x = --a[0];",
+            &format!(
+                "{}\n\nx = js_update_item(a, 0, -1, True)",
+                super::runtime::JS_UPDATE_ITEM
+            ),
+        );
+        // a closure updates the captured variable
+        assert_translate(
+            "let count = 0; const inc = () => count++;",
+            "count = 0\ndef inc():\n    global count\n    return ((count := count + 1) - 1)",
+        );
+        assert_translate(
+            "function counter() { let n = 0; return () => ++n }",
+            "def counter():\n    n = 0\n    def _arrow_1():\n        nonlocal n\n        return (n := n + 1)\n    return _arrow_1",
+        );
+        let source = "let count = 0;\nconst inc = () => count++;\nfunction counter() { let n = 10; return () => --n }\nconst dec = counter();\ninc(); dec();\nconsole.log(inc(), count, dec());";
+        let ast = Parser::new(source).parse().unwrap();
+        let code = super::Ast2Py::default().build(&ast).code;
+        if let Some(output) = run_python(&code) {
+            assert_eq!(output, "1 2 8\n");
+        }
+    }
+    #[test]
+    fn test_destructuring() {
+        assert_translate("let x, y = 1;", "x = None\ny = 1");
+        assert_translate("const [a, b] = arr;", "a, b = (list(arr) + [None] * 2)[:2]");
+        assert_translate("const [a] = arr;", "a, = (list(arr) + [None])[:1]");
+        assert_translate(
+            "let [, a, ...rest] = f();",
+            "_value_1 = list(f())\n_, a = (_value_1 + [None] * 2)[:2]\nrest = _value_1[2:]",
+        );
+        assert_translate("let [...rest] = f();", "rest = list(f())");
+        assert_translate(
+            "let [a, [b, c]] = arr;",
+            "a, _value_1 = (list(arr) + [None] * 2)[:2]\nb, c = (list(_value_1) + [None] * 2)[:2]",
+        );
+        let js_get = super::runtime::JS_GET;
+        assert_translate(
+            "let [a = 1, {b}] = arr;",
+            &format!("{}\n\na, _value_1 = (list(arr) + [None] * 2)[:2]\na = 1 if a is None else a\nb = js_get(_value_1, \"b\")", js_get),
+        );
+        assert_translate(
+            "const {a, b: c, d = 2, ...rest} = obj;",
+            &format!("{}\n\na = js_get(obj, \"a\")\nc = js_get(obj, \"b\")\nd = js_get(obj, \"d\", 2)\nrest = {{k: v for k, v in obj.items() if k not in (\"a\", \"b\", \"d\")}}", js_get),
+        );
+        // the source is evaluated once
+        assert_translate(
+            "const {a, b} = f();",
+            &format!(
+                "{}\n\n_value_1 = f()\na = js_get(_value_1, \"a\")\nb = js_get(_value_1, \"b\")",
+                js_get
+            ),
+        );
+        assert_translate(
+            "const {a: {b}} = obj;",
+            &format!("{}\n\nb = js_get(js_get(obj, \"a\"), \"b\")", js_get),
+        );
+        assert_translate(
+            "for (const [k, v] of pairs) f(k, v)",
+            "for _value_1 in pairs:\n    k, v = (list(_value_1) + [None] * 2)[:2]\n    f(k, v)",
+        );
+        assert_translate(
+            "for (const {name} of people) f(name)",
+            &format!(
+                "{}\n\nfor _value_1 in people:\n    name = js_get(_value_1, \"name\")\n    f(name)",
+                js_get
+            ),
+        );
+    }
+    #[test]
+    fn test_destructuring_runs() {
+        // arrays shorter and longer than the pattern, objects with missing keys, instances of
+        // classes and missing nested objects
+        let source = "class P { constructor() { this.p = 2 } }\nfunction f(arr, obj) {\n  const [a, b = 7, ...rest] = arr;\n  const [c, d] = arr;\n  const {p, q, r: {s} = {}, t: {u}} = obj;\n  return [a, b, rest, c, d, p, q, s, u];\n}\nconsole.log(f([1], {p: 1}));\nconsole.log(f([1, 2, 3, 4], new P()));";
+        let ast = Parser::new(source).parse().unwrap();
+        let code = super::Ast2Py::default().build(&ast).code;
+        let Some(output) = run_python(&code) else {
+            return;
+        };
+        assert_eq!(
+            output,
+            "[1, 7, [], 1, None, 1, None, None, None]\n[1, 2, [3, 4], 1, 2, 2, None, None, None]\n"
+        );
+    }
+    #[test]
+    fn test_new_expression() {
+        assert_translate("p = new Point(1, 2);", "p = Point(1, 2)");
+        assert_translate("p = new a.b.C;", "p = a.b.C()");
+        assert_translate("m = new Map(); s = new Set(xs);", "m = dict()\ns = set(xs)");
+        assert_translate(
+            "e = new Error('oops');",
+            &format!("{}\n\ne = JSError('oops')", super::runtime::JS_ERROR),
+        );
+    }
+    #[test]
+    fn test_this() {
+        assert_translate(
+            "class A { constructor() { if (new.target === A) f(); this.x = 1 } get() { return () => this.x } static make() { return new this() } }",
+            "class A:\n    def __init__(self):\n        if type(self) is A:\n            f()\n        self.x = 1\n    def get(self):\n        return lambda: self.x\n    @staticmethod\n    def make():\n        return A()",
+        );
+        // outside of methods `this` is the global object
+        assert_translate(
+            "this.x = 1; function f() { return new.target }",
+            &format!(
+                "import types\n\n{}\n\njs_this.x = 1\ndef f():\n    return None",
+                super::runtime::JS_THIS
+            ),
+        );
+        // static fields using `this` are assigned after the class
+        assert_translate(
+            "class A { static a = 1; static b = this.a + 1; static c = 3; static f = () => this.b }",
+            "class A:\n    a = 1\nA.b = A.a + 1\nA.c = 3\nA.f = lambda: A.b",
+        );
+        let source = "class A { static a = 1; static b = this.a + 1; static twice() { return this.b * 2 } static c = this.twice() }\nconsole.log(A.a, A.b, A.c);";
+        let ast = Parser::new(source).parse().unwrap();
+        let code = super::Ast2Py::default().build(&ast).code;
+        if let Some(output) = run_python(&code) {
+            assert_eq!(output, "1 2 4\n");
+        }
+    }
+    #[test]
     fn test_empty_if_else() {
         let source = "if (true){}else{};";
         let expected = "if True:\n    pass\nelse:\n    pass";
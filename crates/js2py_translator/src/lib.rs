@@ -1,4 +1,7 @@
+mod analysis;
 mod ast2py;
+pub mod mangle;
+mod runtime;
 pub use ast2py::*;
 
 pub fn make_indent(s: &str, indent: usize) -> String {
@@ -3,22 +3,42 @@ use js2py_translator::Ast2Py;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <source file>", args[0]);
+    // `--string-keys`: `for...in` over a list yields string indices like in JavaScript
+    let string_keys = args[1..].iter().any(|arg| arg == "--string-keys");
+    let files: Vec<_> = args[1..]
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    if files.len() != 1 {
+        eprintln!("Usage: {} [--string-keys] <source file>", args[0]);
         std::process::exit(1);
     }
 
-    let filename = &args[1];
+    let filename = files[0];
 
     let source_code = std::fs::read_to_string(filename).unwrap();
 
     let mut parser = Parser::new(&source_code);
-    let ast = parser.parse().unwrap();
+    // translate what could be parsed, the broken regions are left as comments
+    let (ast, errors) = parser.parse_with_recovery();
+    for e in &errors {
+        eprintln!(
+            "{}\n",
+            e.render(&source_code, parser.line_index(), filename)
+        );
+    }
 
-    let python_code = Ast2Py::default().build(&ast).code;
+    let ret = Ast2Py::default().with_string_keys(string_keys).build(&ast);
+    for warning in &ret.warnings {
+        eprintln!("warning: {}", warning);
+    }
 
     let output_path = format!("{}.py", filename);
-    std::fs::write(output_path, python_code).unwrap();
+    std::fs::write(output_path, ret.code).unwrap();
 
-    println!("wrote python code into {}.py", filename)
+    println!("wrote python code into {}.py", filename);
+    if !errors.is_empty() {
+        eprintln!("{} syntax errors were left as TODO comments", errors.len());
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,181 @@
+//! Mapping of JavaScript identifiers to Python identifiers.
+//!
+//! Names that are already valid Python identifiers are kept as is. Everything else is encoded
+//! with escape sequences of the form `_X_`:
+//!
+//! - `$` becomes `_S_`
+//! - a character that Python does not accept becomes `_U<HEX>_`
+//! - a Python keyword gets the suffix `_K_`
+//! - a `_` that would be read as the start of an escape sequence becomes `_L_`
+//!
+//! The last rule escapes JavaScript names that look like encoded names, so the mapping is
+//! collision-free and [`demangle`] restores the original name.
+
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::UnicodeNormalization;
+
+#[rustfmt::skip]
+const PYTHON_KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+    "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if",
+    "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try",
+    "while", "with", "yield",
+];
+
+/// Convert a JavaScript identifier, which may contain `\u` escapes, to a Python identifier
+pub fn mangle(name: &str) -> String {
+    let name = unescape_identifier(name);
+    // Python normalizes identifiers to NFKC, if the name would change, escape all non-ASCII
+    let escape_non_ascii = name.nfkc().ne(name.chars());
+    let chunks = name
+        .char_indices()
+        .map(|(i, c)| match c {
+            '$' => "_S_".to_string(),
+            c if (c.is_ascii() || !escape_non_ascii) && is_python_identifier_char(c, i == 0) => {
+                c.to_string()
+            }
+            c => format!("_U{:X}_", c as u32),
+        })
+        .collect::<Vec<_>>();
+    // from right to left, so that the text following each `_` is known
+    let mut out = String::with_capacity(name.len());
+    for chunk in chunks.iter().rev() {
+        if chunk == "_" && parse_escape(&format!("_{}", out)).is_some() {
+            out.insert_str(0, "_L_");
+        } else {
+            out.insert_str(0, chunk);
+        }
+    }
+    if PYTHON_KEYWORDS.contains(&out.as_str()) {
+        out.push_str("_K_");
+    }
+    out
+}
+
+/// Restore the JavaScript identifier from the result of [`mangle`]
+pub fn demangle(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut i = 0;
+    while i < name.len() {
+        if let Some((decoded, len)) = parse_escape(&name[i..]) {
+            out.push_str(&decoded);
+            i += len;
+        } else {
+            let c = name[i..].chars().next().unwrap();
+            out.push(c);
+            i += c.len_utf8();
+        }
+    }
+    out
+}
+
+/// Parse an escape sequence at the start of `s`, returns the decoded text and its length
+fn parse_escape(s: &str) -> Option<(String, usize)> {
+    let rest = s.strip_prefix('_')?;
+    let decoded = match rest.as_bytes().first()? {
+        b'S' => "$".to_string(),
+        b'L' => "_".to_string(),
+        b'K' => String::new(),
+        b'U' => {
+            let hex_len = rest[1..]
+                .find(|c: char| !matches!(c, '0'..='9' | 'A'..='F'))
+                .unwrap_or(rest.len() - 1);
+            let code = u32::from_str_radix(&rest[1..1 + hex_len], 16).ok()?;
+            let len = 1 + hex_len;
+            return (rest[len..].starts_with('_'))
+                .then(|| char::from_u32(code))
+                .flatten()
+                .map(|c| (c.to_string(), len + 2));
+        }
+        _ => return None,
+    };
+    rest[1..].starts_with('_').then_some((decoded, 3))
+}
+
+fn is_python_identifier_char(c: char, is_start: bool) -> bool {
+    // ZWNJ and ZWJ are only accepted since Unicode 15.1 (Python 3.13)
+    if matches!(c, '\u{200C}' | '\u{200D}') {
+        return false;
+    }
+    let valid = if is_start {
+        c == '_' || unicode_ident::is_xid_start(c)
+    } else {
+        unicode_ident::is_xid_continue(c)
+    };
+    // combining marks could be composed with the previous character by NFKC
+    valid && canonical_combining_class(c) == 0 && c.to_string().nfkc().eq([c])
+}
+
+/// Decode the `\uXXXX` and `\u{X}` escapes of a JavaScript identifier
+fn unescape_identifier(name: &str) -> String {
+    if !name.contains('\\') {
+        return name.to_string();
+    }
+    let mut out = String::with_capacity(name.len());
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        chars.next(); // skip `u`, guaranteed by the lexer
+        let hex = if chars.peek() == Some(&'{') {
+            chars.next();
+            chars.by_ref().take_while(|&c| c != '}').collect::<String>()
+        } else {
+            chars.by_ref().take(4).collect::<String>()
+        };
+        let code = u32::from_str_radix(&hex, 16).unwrap_or_default();
+        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mangle() {
+        assert_eq!(mangle("item2"), "item2");
+        assert_eq!(mangle("café"), "café");
+        assert_eq!(mangle("__proto__"), "__proto__");
+        assert_eq!(mangle("$el"), "_S_el");
+        assert_eq!(mangle("a$"), "a_S_");
+        assert_eq!(mangle("_S_el"), "_L_S_el");
+        assert_eq!(mangle("lambda"), "lambda_K_");
+        assert_eq!(mangle("\\u0061bc"), "abc");
+        assert_eq!(mangle("a\u{200D}b"), "a_U200D_b");
+        assert_eq!(mangle("ａｂｃ"), "_UFF41__UFF42__UFF43_");
+    }
+
+    #[test]
+    fn test_demangle_roundtrip() {
+        let names = [
+            "item2",
+            "$el",
+            "_0x1f",
+            "café",
+            "ａｂｃ",
+            "_S_el",
+            "_L_",
+            "a_K_",
+            "None",
+            "a\u{200D}b",
+            "$$",
+            "_U41_",
+            "_U_",
+            "x_",
+            "_",
+            "_S$",
+            "_U4$",
+        ];
+        for name in names {
+            assert_eq!(demangle(&mangle(name)), name);
+        }
+        let mut mangled = names.map(mangle).to_vec();
+        mangled.sort();
+        mangled.dedup();
+        assert_eq!(mangled.len(), names.len());
+    }
+}
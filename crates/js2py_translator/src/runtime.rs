@@ -0,0 +1,78 @@
+//! Python definitions the translated code depends on.
+//!
+//! A definition is emitted once at the top of the translated program, after the imports, when
+//! the translation of some construct uses it.
+
+/// The exception raised by `throw`, a JavaScript program can throw any value so the value is
+/// wrapped and exposed as `message` like an `Error` would
+pub(crate) const JS_ERROR: &str = r#"class JSError(Exception):
+    def __init__(self, value=None):
+        super().__init__(value)
+        self.value = value
+        self.message = "" if value is None else str(value)"#;
+
+/// The decorator of a static getter, a property read from the class
+pub(crate) const JS_CLASSPROPERTY: &str = r#"class js_classproperty:
+    def __init__(self, fget):
+        self.fget = fget
+    def __get__(self, obj, cls=None):
+        return self.fget()"#;
+
+/// `this` outside of methods, standing for the global object
+pub(crate) const JS_THIS: &str = "js_this = types.SimpleNamespace()";
+
+/// The keys `for...in` iterates: the indices of a list or a string, and the keys of anything
+/// else
+pub(crate) const JS_KEYS: &str = r#"def js_keys(obj):
+    if isinstance(obj, (list, str)):
+        return range(len(obj))
+    return list(obj)"#;
+
+/// The keys `for...in` iterates, with the indices of a list or a string as strings like in
+/// JavaScript
+pub(crate) const JS_STRING_KEYS: &str = r#"def js_string_keys(obj):
+    if isinstance(obj, (list, str)):
+        return [str(i) for i in range(len(obj))]
+    return list(obj)"#;
+
+/// A function replacing the matches of `re.sub` called as JavaScript does, with the match, the
+/// groups, the offset and the whole string, as many of them as it declares parameters for
+pub(crate) const JS_REPLACER: &str = r#"def js_replacer(fn):
+    code = fn.__code__
+    count = None if code.co_flags & 0x04 else code.co_argcount  # 0x04: *args
+    def replace(match):
+        args = (match.group(0), *match.groups(), match.start(), match.string)
+        return str(fn(*args[:count]))
+    return replace"#;
+
+/// `obj.name++` and its variants where the value is used, `prefix` chooses between the new and
+/// the old value
+pub(crate) const JS_UPDATE_ATTR: &str = r#"def js_update_attr(obj, name, delta, prefix):
+    value = getattr(obj, name)
+    setattr(obj, name, value + delta)
+    return value + delta if prefix else value"#;
+
+/// `obj[key]++` and its variants where the value is used
+pub(crate) const JS_UPDATE_ITEM: &str = r#"def js_update_item(obj, key, delta, prefix):
+    value = obj[key]
+    obj[key] = value + delta
+    return value + delta if prefix else value"#;
+
+/// `obj.name = value` where the value is used
+pub(crate) const JS_ASSIGN_ATTR: &str = r#"def js_assign_attr(obj, name, value):
+    setattr(obj, name, value)
+    return value"#;
+
+/// `obj[key] = value` where the value is used
+pub(crate) const JS_ASSIGN_ITEM: &str = r#"def js_assign_item(obj, key, value):
+    obj[key] = value
+    return value"#;
+
+/// The property `key` of `obj` read by an object pattern: an item of a dict and an attribute of
+/// anything else, `None` if it is missing or `obj` is `None`, in which case `default` is used
+pub(crate) const JS_GET: &str = r#"def js_get(obj, key, default=None):
+    if isinstance(obj, dict):
+        value = obj.get(key)
+    else:
+        value = getattr(obj, str(key), None)
+    return default if value is None else value"#;